@@ -1,93 +1,219 @@
+mod passport;
+
+use crate::passport::{passports_to_batch, passports_to_json, Passport};
 use regex::Regex;
 use std::collections::{HashMap, HashSet};
 use std::env;
+use std::fmt::Display;
 use std::fs;
 
 const COLOR_RE_STR: &str = r"^#[0-9a-f]{6}$";
 const DATA_RE_STR: &str = r"(\w+):(\S+)";
-const HEIGHT_RE_STR: &str = r"^(\d+)(cm|in)$";
+const HEIGHT_RE_STR: &str = r"^(\d+)(\D*)$";
 const PASSPORT_RE_STR: &str = r"^\d{9}$";
 const SPLIT_RE_STR: &str = r"\n\n";
 
+const REQUIRED_FIELDS: &[&str] = &["byr", "iyr", "eyr", "hgt", "hcl", "ecl", "pid"];
+const EYE_COLORS: &[&str] = &["amb", "blu", "brn", "gry", "grn", "hzl", "oth"];
+
 type Profiles<'a> = Vec<HashMap<&'a str, &'a str>>;
 
-/// From a traveler database String, create a vector of profiles (HashMaps)
-fn get_profiles<'a>(database: &'a String) -> Profiles<'a> {
-    let split_re = Regex::new(SPLIT_RE_STR).unwrap();
-    let data_re = Regex::new(DATA_RE_STR).unwrap();
+/// A profile's fields in the order they appear in the database, duplicates included
+type RawProfile<'a> = Vec<(&'a str, &'a str)>;
+
+/// A reason a single profile fails validation
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum Violation<'a> {
+    MissingField(&'a str),
+    /// Field and the value that couldn't be parsed
+    Unparseable(&'a str, &'a str),
+    /// Field and the parsed value outside of the field's range
+    OutOfRange(&'a str, usize),
+    /// Height unit other than cm or in
+    UnknownUnit(&'a str),
+    DuplicateKey(&'a str),
+    UnexpectedField(&'a str),
+}
+
+impl<'a> Violation<'a> {
+    /// A description of the violation without the offending value, for grouping similar failures
+    fn reason(&self) -> String {
+        match self {
+            Violation::MissingField(field) => format!("missing {}", field),
+            Violation::Unparseable(field, _) => format!("unparseable {}", field),
+            Violation::OutOfRange(field, _) => format!("{} out of range", field),
+            Violation::UnknownUnit(_) => "unknown hgt unit".to_string(),
+            Violation::DuplicateKey(field) => format!("duplicate {}", field),
+            Violation::UnexpectedField(_) => "unexpected field".to_string(),
+        }
+    }
+}
+
+impl<'a> Display for Violation<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Violation::MissingField(field) => write!(f, "missing field {}", field),
+            Violation::Unparseable(field, value) =>
+                write!(f, "could not parse {} value \"{}\"", field, value),
+            Violation::OutOfRange(field, value) =>
+                write!(f, "{} value {} is out of range", field, value),
+            Violation::UnknownUnit(unit) => write!(f, "unknown hgt unit \"{}\"", unit),
+            Violation::DuplicateKey(field) => write!(f, "field {} appears more than once", field),
+            Violation::UnexpectedField(field) => write!(f, "unexpected field {}", field),
+        }
+    }
+}
+
+/// Compiled patterns for checking individual passport fields
+struct Validator {
+    color_re: Regex,
+    height_re: Regex,
+    passport_re: Regex,
+}
+
+impl Validator {
+    fn new() -> Self {
+        Self {
+            color_re: Regex::new(COLOR_RE_STR).unwrap(),
+            height_re: Regex::new(HEIGHT_RE_STR).unwrap(),
+            passport_re: Regex::new(PASSPORT_RE_STR).unwrap(),
+        }
+    }
 
-    split_re.split(&database[..]).map(|profile_str| {
-        let mut profile: HashMap<&str, &str> = HashMap::new();
+    /// Check a single field's value, returning the violation if there is one
+    fn check_field<'a>(&self, field: &'a str, value: &'a str) -> Option<Violation<'a>> {
+        let year_in = |min: usize, max: usize| match value.parse::<usize>() {
+            Ok(year) if (min..=max).contains(&year) => None,
+            Ok(year) => Some(Violation::OutOfRange(field, year)),
+            Err(_) => Some(Violation::Unparseable(field, value)),
+        };
 
-        for attr in data_re.captures_iter(profile_str) {
-            profile.insert(
-                attr.get(1).unwrap().as_str(),
-                attr.get(2).unwrap().as_str(),
-            );
+        match field {
+            "byr" => year_in(1920, 2002),
+            "iyr" => year_in(2010, 2020),
+            "eyr" => year_in(2020, 2030),
+            "hgt" => {
+                let captures = match self.height_re.captures(value) {
+                    Some(captures) => captures,
+                    None => return Some(Violation::Unparseable(field, value)),
+                };
+                let unit = captures.get(2).unwrap().as_str();
+                let height: usize = match captures.get(1).unwrap().as_str().parse() {
+                    Ok(height) => height,
+                    Err(_) => return Some(Violation::Unparseable(field, value)),
+                };
+                let range = match unit {
+                    "cm" => 150..=193,
+                    "in" => 59..=76,
+                    _ => return Some(Violation::UnknownUnit(unit)),
+                };
+                if range.contains(&height) {
+                    None
+                } else {
+                    Some(Violation::OutOfRange(field, height))
+                }
+            }
+            "hcl" if !self.color_re.is_match(value) => Some(Violation::Unparseable(field, value)),
+            "ecl" if !EYE_COLORS.contains(&value) => Some(Violation::Unparseable(field, value)),
+            "pid" if !self.passport_re.is_match(value) => Some(Violation::Unparseable(field, value)),
+            "hcl" | "ecl" | "pid" | "cid" => None,
+            _ => Some(Violation::UnexpectedField(field)),
         }
+    }
+
+    /// Find every reason a profile fails validation, empty if the profile is valid
+    fn validate<'a>(&self, profile: &RawProfile<'a>) -> Vec<Violation<'a>> {
+        let mut seen: HashSet<&str> = HashSet::new();
+        let mut violations: Vec<Violation<'a>> = Vec::new();
+
+        for &(field, value) in profile.iter() {
+            if !seen.insert(field) {
+                violations.push(Violation::DuplicateKey(field));
+            }
+            if let Some(violation) = self.check_field(field, value) {
+                violations.push(violation);
+            }
+        }
+
+        for field in REQUIRED_FIELDS.iter() {
+            if !seen.contains(field) {
+                violations.push(Violation::MissingField(field));
+            }
+        }
+
+        violations
+    }
+}
+
+/// From a traveler database, create a vector of profiles with fields in their original order
+fn get_raw_profiles(database: &str) -> Vec<RawProfile<'_>> {
+    let split_re = Regex::new(SPLIT_RE_STR).unwrap();
+    let data_re = Regex::new(DATA_RE_STR).unwrap();
 
-        profile
+    split_re.split(database).map(|profile_str| {
+        data_re.captures_iter(profile_str).map(|attr| (
+            attr.get(1).unwrap().as_str(),
+            attr.get(2).unwrap().as_str(),
+        )).collect()
     }).collect()
 }
 
+/// From a traveler database String, create a vector of profiles (HashMaps)
+fn get_profiles(database: &str) -> Profiles<'_> {
+    get_raw_profiles(database)
+        .into_iter()
+        .map(|profile| profile.into_iter().collect())
+        .collect()
+}
+
 /// Count valid passports in the profile list
 fn part1(profiles: &Profiles) -> usize {
-    let required_fields = vec!["byr", "iyr", "eyr", "hgt", "hcl", "ecl", "pid"];
-
     profiles.iter().filter(|profile|
-        required_fields.iter().all(|key| profile.contains_key(key))
+        REQUIRED_FIELDS.iter().all(|key| profile.contains_key(key))
     ).count()
 }
 
-fn opt_parse(num_s: &&str) -> Option<usize> {
-    num_s.parse().map_or(None, |num| Some(num))
+fn part2(profiles: &Profiles) -> usize {
+    let validator = Validator::new();
+
+    profiles.iter().filter(|profile|
+        REQUIRED_FIELDS.iter().all(|&key|
+            profile
+                .get(key)
+                .is_some_and(|value| validator.check_field(key, value).is_none())
+        )
+    ).count()
 }
 
-fn part2(profiles: &Profiles) -> usize {
-    let height_re = Regex::new(HEIGHT_RE_STR).unwrap();
-    let color_re = Regex::new(COLOR_RE_STR).unwrap();
-    let passport_re = Regex::new(PASSPORT_RE_STR).unwrap();
-    let mut valid_eye_colors: HashSet<&str> = HashSet::new();
-    for color in vec![
-        "amb",
-        "blu",
-        "brn",
-        "gry",
-        "grn",
-        "hzl",
-        "oth",
-    ].into_iter() {
-        valid_eye_colors.insert(color);
+/// Summarize the most common reasons profiles fail validation, most frequent first
+fn violation_report(profiles: &[RawProfile]) -> Vec<(String, usize)> {
+    let validator = Validator::new();
+    let mut frequencies: HashMap<String, usize> = HashMap::new();
+
+    for profile in profiles.iter() {
+        for violation in validator.validate(profile) {
+            frequencies
+                .entry(violation.reason())
+                .and_modify(|f| *f += 1)
+                .or_insert(1);
+        }
     }
 
-    profiles.iter().filter(|profile| {
-        profile.get("byr")
-            .and_then(opt_parse)
-            .map_or(false, |val| val >= 1920 && val <= 2002) &&
-        profile.get("iyr")
-            .and_then(opt_parse)
-            .map_or(false, |val| val >= 2010 && val <= 2020) &&
-        profile.get("eyr")
-            .and_then(opt_parse)
-            .map_or(false, |val| val >= 2020 && val <= 2030) &&
-        profile.get("hgt")
-            .and_then(|sval| height_re.captures(sval))
-            .and_then(|captures| captures.get(2).zip(captures.get(1)))
-            .and_then(|(unit, num_s)|
-                opt_parse(&num_s.as_str())
-                    .map(|num| match unit.as_str() {
-                        "cm" => num >= 150 && num <= 193,
-                        "in" => num >= 59 && num <= 76,
-                        _ => panic!("Regex should have missed"),
-                    })
-            ).unwrap_or(false) &&
-        profile.get("hcl")
-            .map_or(false, |hcl| color_re.is_match(hcl)) &&
-        profile.get("ecl")
-            .map_or(false, |sval| valid_eye_colors.contains(sval)) &&
-        profile.get("pid")
-            .map_or(false, |pid| passport_re.is_match(pid))
-    }).count()
+    let mut report: Vec<(String, usize)> = frequencies.into_iter().collect();
+    report.sort_unstable_by(|(reason_a, count_a), (reason_b, count_b)|
+        count_b.cmp(count_a).then(reason_a.cmp(reason_b))
+    );
+    report
+}
+
+/// Build typed passports from every profile that passes validation
+fn get_passports(profiles: &[RawProfile]) -> Vec<Passport> {
+    let validator = Validator::new();
+
+    profiles
+        .iter()
+        .filter_map(|profile| Passport::from_profile(&validator, profile).ok())
+        .collect()
 }
 
 fn main() {
@@ -95,6 +221,45 @@ fn main() {
     let filename = &args[1];
 
     let contents = fs::read_to_string(filename).expect("Error opening file");
+
+    // Pass "report" after the filename to see why passports fail instead of the answers
+    if args.get(2).map(String::as_str) == Some("report") {
+        let raw_profiles = get_raw_profiles(&contents);
+        let validator = Validator::new();
+        let mut invalid = 0;
+
+        for (index, profile) in raw_profiles.iter().enumerate() {
+            let violations = validator.validate(profile);
+            if violations.is_empty() {
+                continue;
+            }
+            invalid += 1;
+            println!("Profile {}:", index + 1);
+            for violation in violations {
+                println!("  {}", violation);
+            }
+        }
+
+        println!("\n{} of {} profiles invalid", invalid, raw_profiles.len());
+        for (reason, count) in violation_report(&raw_profiles) {
+            println!("{:>6} {}", count, reason);
+        }
+        return;
+    }
+
+    // Pass "json" or "batch" after the filename to write out only the valid passports
+    match args.get(2).map(String::as_str) {
+        Some("json") => {
+            println!("{}", passports_to_json(&get_passports(&get_raw_profiles(&contents))));
+            return;
+        }
+        Some("batch") => {
+            println!("{}", passports_to_batch(&get_passports(&get_raw_profiles(&contents))));
+            return;
+        }
+        _ => {}
+    }
+
     let profiles = get_profiles(&contents);
 
     println!("Part 1: {}", part1(&profiles));
@@ -118,4 +283,65 @@ mod tests {
         let profiles = get_profiles(&database);
         assert_eq!(part2(&profiles), 4);
     }
+
+    #[test]
+    fn validate_reports_violations() {
+        let validator = Validator::new();
+        let profiles = get_raw_profiles(include_str!("sample2"));
+
+        assert_eq!(validator.validate(&profiles[0]), vec![Violation::MissingField("byr")]);
+        assert_eq!(validator.validate(&profiles[1]), vec![
+            Violation::OutOfRange("eyr", 1972),
+            Violation::UnknownUnit(""),
+            Violation::Unparseable("pid", "186cm"),
+        ]);
+        assert!(validator.validate(&profiles[5]).is_empty());
+
+        let odd = get_raw_profiles("byr:1980 byr:19x0 hgt:60ft foo:bar");
+        assert_eq!(validator.validate(&odd[0]), vec![
+            Violation::DuplicateKey("byr"),
+            Violation::Unparseable("byr", "19x0"),
+            Violation::UnknownUnit("ft"),
+            Violation::UnexpectedField("foo"),
+            Violation::MissingField("iyr"),
+            Violation::MissingField("eyr"),
+            Violation::MissingField("hcl"),
+            Violation::MissingField("ecl"),
+            Violation::MissingField("pid"),
+        ]);
+    }
+
+    #[test]
+    fn report_orders_by_frequency() {
+        let profiles = get_raw_profiles(include_str!("sample"));
+        let report = violation_report(&profiles);
+        assert_eq!(report[0], ("missing byr".to_string(), 1));
+        assert_eq!(report.iter().map(|(_, count)| count).sum::<usize>(), 2);
+    }
+
+    #[test]
+    fn passports_round_trip() {
+        let passports = get_passports(&get_raw_profiles(include_str!("sample2")));
+        assert_eq!(passports.len(), 4);
+        assert_eq!(passports[0].height, passport::Height {
+            value: 74,
+            unit: passport::HeightUnit::Inches,
+        });
+        assert_eq!(passports[0].eye_color, passport::EyeColor::Green);
+        assert_eq!(passports[0].passport_id, "087499704");
+
+        let batch = passports_to_batch(&passports);
+        assert_eq!(get_passports(&get_raw_profiles(&batch)), passports);
+    }
+
+    #[test]
+    fn passport_to_json() {
+        let passports = get_passports(&get_raw_profiles(include_str!("sample")));
+        assert_eq!(
+            passports[0].to_json(),
+            "{\"byr\":1937,\"iyr\":2017,\"eyr\":2020,\"hgt\":{\"value\":183,\"unit\":\"cm\"},\
+             \"hcl\":\"#fffffd\",\"ecl\":\"gry\",\"pid\":\"860033327\",\"cid\":\"147\"}"
+        );
+        assert!(passports[1].to_json().ends_with("\"cid\":null}"));
+    }
 }
@@ -0,0 +1,196 @@
+use crate::{RawProfile, Validator, Violation};
+use std::fmt::Display;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum HeightUnit {
+    Centimeters,
+    Inches,
+}
+
+impl HeightUnit {
+    fn from_str(unit: &str) -> Option<Self> {
+        match unit {
+            "cm" => Some(HeightUnit::Centimeters),
+            "in" => Some(HeightUnit::Inches),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            HeightUnit::Centimeters => "cm",
+            HeightUnit::Inches => "in",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Height {
+    pub value: usize,
+    pub unit: HeightUnit,
+}
+
+impl Display for Height {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.value, self.unit.as_str())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum EyeColor {
+    Amber,
+    Blue,
+    Brown,
+    Gray,
+    Green,
+    Hazel,
+    Other,
+}
+
+impl EyeColor {
+    fn from_str(color: &str) -> Option<Self> {
+        match color {
+            "amb" => Some(EyeColor::Amber),
+            "blu" => Some(EyeColor::Blue),
+            "brn" => Some(EyeColor::Brown),
+            "gry" => Some(EyeColor::Gray),
+            "grn" => Some(EyeColor::Green),
+            "hzl" => Some(EyeColor::Hazel),
+            "oth" => Some(EyeColor::Other),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            EyeColor::Amber => "amb",
+            EyeColor::Blue => "blu",
+            EyeColor::Brown => "brn",
+            EyeColor::Gray => "gry",
+            EyeColor::Green => "grn",
+            EyeColor::Hazel => "hzl",
+            EyeColor::Other => "oth",
+        }
+    }
+}
+
+/// A passport whose fields have all passed validation
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Passport {
+    pub birth_year: usize,
+    pub issue_year: usize,
+    pub expiration_year: usize,
+    pub height: Height,
+    pub hair_color: String,
+    pub eye_color: EyeColor,
+    /// Kept as a string since leading zeroes are significant
+    pub passport_id: String,
+    pub country_id: Option<String>,
+}
+
+/// Quote a string for JSON output
+fn json_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+impl Passport {
+    /// Build a passport from a raw profile, or return every reason the profile is invalid
+    pub fn from_profile<'a>(validator: &Validator, profile: &RawProfile<'a>)
+        -> Result<Self, Vec<Violation<'a>>> {
+        let violations = validator.validate(profile);
+        if !violations.is_empty() {
+            return Err(violations);
+        }
+
+        // Validation guarantees every required field is present, unique and well formed
+        let get = |key: &str| profile
+            .iter()
+            .find(|(field, _)| *field == key)
+            .map(|(_, value)| *value);
+        let year = |key: &str| get(key).unwrap().parse().unwrap();
+        let height_str = get("hgt").unwrap();
+        let (value_str, unit_str) = height_str.split_at(height_str.len() - 2);
+
+        Ok(Self {
+            birth_year: year("byr"),
+            issue_year: year("iyr"),
+            expiration_year: year("eyr"),
+            height: Height {
+                value: value_str.parse().unwrap(),
+                unit: HeightUnit::from_str(unit_str).unwrap(),
+            },
+            hair_color: get("hcl").unwrap().to_string(),
+            eye_color: EyeColor::from_str(get("ecl").unwrap()).unwrap(),
+            passport_id: get("pid").unwrap().to_string(),
+            country_id: get("cid").map(str::to_string),
+        })
+    }
+
+    /// Write the passport as a single line of the original batch file format
+    pub fn to_batch(&self) -> String {
+        let mut batch = format!(
+            "byr:{} iyr:{} eyr:{} hgt:{} hcl:{} ecl:{} pid:{}",
+            self.birth_year,
+            self.issue_year,
+            self.expiration_year,
+            self.height,
+            self.hair_color,
+            self.eye_color.as_str(),
+            self.passport_id,
+        );
+        if let Some(cid) = &self.country_id {
+            batch.push_str(" cid:");
+            batch.push_str(cid);
+        }
+        batch
+    }
+
+    /// Write the passport as a JSON object keyed by the batch field names
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"byr\":{},\"iyr\":{},\"eyr\":{},\"hgt\":{{\"value\":{},\"unit\":\"{}\"}},\
+             \"hcl\":{},\"ecl\":\"{}\",\"pid\":{},\"cid\":{}}}",
+            self.birth_year,
+            self.issue_year,
+            self.expiration_year,
+            self.height.value,
+            self.height.unit.as_str(),
+            json_string(&self.hair_color),
+            self.eye_color.as_str(),
+            json_string(&self.passport_id),
+            self.country_id.as_deref().map_or("null".to_string(), json_string),
+        )
+    }
+}
+
+/// Write passports back out as a batch file, separated by blank lines
+pub fn passports_to_batch(passports: &[Passport]) -> String {
+    passports
+        .iter()
+        .map(Passport::to_batch)
+        .collect::<Vec<String>>()
+        .join("\n\n")
+}
+
+/// Write passports as a JSON array
+pub fn passports_to_json(passports: &[Passport]) -> String {
+    format!(
+        "[{}]",
+        passports
+            .iter()
+            .map(Passport::to_json)
+            .collect::<Vec<String>>()
+            .join(",")
+    )
+}
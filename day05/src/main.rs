@@ -1,11 +1,110 @@
+use std::collections::HashSet;
 use std::env;
+use std::fmt::Display;
 use std::fs;
+use std::ops::RangeInclusive;
 
 enum SpaceHalf {
     High,
     Low,
 }
 
+/// How many characters of a boarding pass are used for the row and for the column
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct SeatLayout {
+    row_bits: usize,
+    col_bits: usize,
+}
+
+/// The layout of the plane in the puzzle: 128 rows of 8 seats
+const DEFAULT_LAYOUT: SeatLayout = SeatLayout { row_bits: 7, col_bits: 3 };
+
+/// The most seats a seat map will draw, enough for 256 rows of 256
+const MAX_MAP_SEATS: usize = 1 << 16;
+
+#[derive(Debug, Eq, PartialEq)]
+enum PassError {
+    /// Expected and actual length of the pass
+    WrongLength(usize, usize),
+    /// Character and its index in the pass
+    UnexpectedChar(char, usize),
+    /// A row and column that don't fit in the layout
+    SeatOutOfRange(usize, usize),
+}
+
+impl Display for PassError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PassError::WrongLength(expected, actual) =>
+                write!(f, "expected a {} character pass, got {}", expected, actual),
+            PassError::UnexpectedChar(c, index) =>
+                write!(f, "unexpected character {} at position {}", c, index),
+            PassError::SeatOutOfRange(row, col) =>
+                write!(f, "row {} column {} is outside the plane", row, col),
+        }
+    }
+}
+
+/// Seat ids with no boarding pass, split by where they fall in the plane
+#[derive(Debug, Default, Eq, PartialEq)]
+struct SeatGaps {
+    /// Missing seats before the first taken seat, where the plane has no seats
+    front: Option<RangeInclusive<usize>>,
+    /// Missing seats after the last taken seat
+    back: Option<RangeInclusive<usize>>,
+    /// Contiguous blocks of missing seats between taken seats
+    gaps: Vec<RangeInclusive<usize>>,
+}
+
+impl SeatGaps {
+    /// Every missing seat between taken seats
+    fn missing_seats(&self) -> impl Iterator<Item=usize> + '_ {
+        self.gaps.iter().flat_map(|gap| gap.clone())
+    }
+}
+
+/// Row and column bits that give more seats than a seat id can count
+#[derive(Debug, Eq, PartialEq)]
+struct LayoutTooLarge(usize, usize);
+
+impl Display for LayoutTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} row bits and {} column bits is more than {} bits of seat ids",
+            self.0, self.1, usize::BITS - 1
+        )
+    }
+}
+
+/// The number of seats in a layout too big to draw as a map
+#[derive(Debug, Eq, PartialEq)]
+struct MapTooLarge(usize);
+
+impl Display for MapTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} seats is more than the {} a map can show", self.0, MAX_MAP_SEATS)
+    }
+}
+
+impl SeatLayout {
+    /// A layout whose seat ids all fit in a usize
+    fn new(row_bits: usize, col_bits: usize) -> Result<Self, LayoutTooLarge> {
+        match row_bits.checked_add(col_bits) {
+            Some(bits) if bits < usize::BITS as usize => Ok(Self { row_bits, col_bits }),
+            _ => Err(LayoutTooLarge(row_bits, col_bits)),
+        }
+    }
+
+    fn rows(&self) -> usize {
+        1 << self.row_bits
+    }
+
+    fn cols(&self) -> usize {
+        1 << self.col_bits
+    }
+}
+
 /// From a series of partitions, get a space coordinate
 fn position_from_partitions(partitions: Vec<SpaceHalf>) -> usize {
     partitions.iter().rev()
@@ -15,70 +114,229 @@ fn position_from_partitions(partitions: Vec<SpaceHalf>) -> usize {
                 SpaceHalf::Low => 0
             });
 
-            *state = *state * 2;
+            *state *= 2;
             next
-        }).fold(0, |acc, n| acc + n)
+        }).sum()
+}
+
+/// Turn a coordinate into a series of partitions, most significant first
+fn partitions_from_position(position: usize, bits: usize) -> Vec<SpaceHalf> {
+    (0..bits).rev()
+        .map(|bit| if position >> bit & 1 == 1 {
+            SpaceHalf::High
+        } else {
+            SpaceHalf::Low
+        }).collect()
 }
 
 /// Compute the seat id from a row and column
-fn seat_id_from_position((row, col): (usize, usize)) -> usize {
-    row * 8 + col
+fn seat_id_from_position(layout: &SeatLayout, (row, col): (usize, usize)) -> usize {
+    row * layout.cols() + col
+}
+
+/// Compute the row and column from a seat id
+fn seat_position_from_id(layout: &SeatLayout, id: usize) -> (usize, usize) {
+    (id / layout.cols(), id % layout.cols())
 }
 
 /// From a seat string, get the seat's row and column
-fn seat_position_from_string(seat: &str) -> (usize, usize) {
-    let (row_str, col_str) = seat.split_at(7);
-    let row_partitions: Vec<SpaceHalf> = row_str.chars()
-        .map(|c| match c {
-            'F' => SpaceHalf::Low,
-            'B' => SpaceHalf::High,
-            _ => panic!("Unexpected row char {}", c)
-        }).collect();
-    let col_partitions: Vec<SpaceHalf> = col_str.chars()
-        .map(|c| match c {
-            'L' => SpaceHalf::Low,
-            'R' => SpaceHalf::High,
-            _ => panic!("Unexpected col char {}", c)
-        }).collect();
-    (
+fn seat_position_from_string(layout: &SeatLayout, seat: &str)
+    -> Result<(usize, usize), PassError> {
+    let expected = layout.row_bits + layout.col_bits;
+    let length = seat.chars().count();
+    if length != expected {
+        return Err(PassError::WrongLength(expected, length));
+    }
+
+    let mut row_partitions: Vec<SpaceHalf> = Vec::new();
+    let mut col_partitions: Vec<SpaceHalf> = Vec::new();
+    for (index, c) in seat.chars().enumerate() {
+        match (index < layout.row_bits, c) {
+            (true, 'F') => row_partitions.push(SpaceHalf::Low),
+            (true, 'B') => row_partitions.push(SpaceHalf::High),
+            (false, 'L') => col_partitions.push(SpaceHalf::Low),
+            (false, 'R') => col_partitions.push(SpaceHalf::High),
+            _ => return Err(PassError::UnexpectedChar(c, index)),
+        }
+    }
+
+    Ok((
         position_from_partitions(row_partitions),
         position_from_partitions(col_partitions)
-    )
+    ))
+}
+
+/// From a seat's row and column, get its boarding pass string
+fn seat_string_from_position(layout: &SeatLayout, (row, col): (usize, usize))
+    -> Result<String, PassError> {
+    if row >= layout.rows() || col >= layout.cols() {
+        return Err(PassError::SeatOutOfRange(row, col));
+    }
+
+    let row_chars = partitions_from_position(row, layout.row_bits)
+        .into_iter()
+        .map(|half| match half {
+            SpaceHalf::Low => 'F',
+            SpaceHalf::High => 'B',
+        });
+    let col_chars = partitions_from_position(col, layout.col_bits)
+        .into_iter()
+        .map(|half| match half {
+            SpaceHalf::Low => 'L',
+            SpaceHalf::High => 'R',
+        });
+
+    Ok(row_chars.chain(col_chars).collect())
 }
 
-fn part1<'a>(seats: impl Iterator<Item=&'a str>) -> usize {
+/// From a seat id, get its boarding pass string
+fn seat_string_from_id(layout: &SeatLayout, id: usize) -> Result<String, PassError> {
+    seat_string_from_position(layout, seat_position_from_id(layout, id))
+}
+
+/// Decode every boarding pass into a seat id
+fn parse_seat_ids<'a>(layout: &SeatLayout, seats: impl Iterator<Item=&'a str>)
+    -> Result<Vec<usize>, PassError> {
     seats
-        .map(seat_position_from_string)
-        .map(seat_id_from_position)
+        .map(|seat| seat_position_from_string(layout, seat)
+            .map(|position| seat_id_from_position(layout, position)))
+        .collect()
+}
+
+/// Find the missing seats in the plane, separating the seats missing from the front and back
+fn find_gaps(layout: &SeatLayout, seat_ids: &[usize]) -> SeatGaps {
+    let mut seat_ids = seat_ids.to_vec();
+    seat_ids.sort_unstable();
+    seat_ids.dedup();
+
+    let last_seat = layout.rows() * layout.cols() - 1;
+    let (first, last) = match (seat_ids.first(), seat_ids.last()) {
+        (Some(&first), Some(&last)) => (first, last),
+        _ => return SeatGaps { front: Some(0..=last_seat), ..SeatGaps::default() },
+    };
+
+    SeatGaps {
+        front: if first > 0 { Some(0..=first - 1) } else { None },
+        back: if last < last_seat { Some(last + 1..=last_seat) } else { None },
+        gaps: seat_ids
+            .windows(2)
+            .filter(|window| window[1] - window[0] > 1)
+            .map(|window| window[0] + 1..=window[1] - 1)
+            .collect(),
+    }
+}
+
+/// Render the plane with one line per row, # for taken seats and . for empty ones
+fn render_seat_map(layout: &SeatLayout, seat_ids: &[usize]) -> Result<String, MapTooLarge> {
+    let seats = layout.rows() * layout.cols();
+    if seats > MAX_MAP_SEATS {
+        return Err(MapTooLarge(seats));
+    }
+
+    let taken: HashSet<usize> = seat_ids.iter().copied().collect();
+    let row_width = (layout.rows() - 1).to_string().len();
+
+    Ok((0..layout.rows())
+        .map(|row| {
+            let seats: String = (0..layout.cols())
+                .map(|col| if taken.contains(&seat_id_from_position(layout, (row, col))) {
+                    '#'
+                } else {
+                    '.'
+                }).collect();
+            format!("{:>width$} {}", row, seats, width = row_width)
+        })
+        .collect::<Vec<String>>()
+        .join("\n"))
+}
+
+fn part1(seat_ids: &[usize]) -> usize {
+    seat_ids
+        .iter()
+        .copied()
         .max()
         .unwrap()
 }
 
-fn part2<'a>(seats: impl Iterator<Item=&'a str>) -> usize {
-    let mut seat_ids: Vec<usize> = seats
-        .map(seat_position_from_string)
-        .map(seat_id_from_position)
-        .collect();
+/// Find the only empty seat with taken seats on either side of it
+fn part2(gaps: &SeatGaps) -> Option<usize> {
+    let mut single_gaps = gaps.gaps
+        .iter()
+        .filter(|gap| gap.start() == gap.end())
+        .map(|gap| *gap.start());
 
-    seat_ids.sort_unstable();
+    match (single_gaps.next(), single_gaps.next()) {
+        (Some(seat), None) => Some(seat),
+        _ => None,
+    }
+}
 
-    seat_ids.windows(2)
-        .find_map(|window| {
-            if window[1] - window[0] == 2{
-                Some(window[1] - 1)
-            } else {
-                None
-            }
-        }).expect("404 seat not found")
+/// Describe a range of seat ids in terms of their rows and columns
+fn describe_range(layout: &SeatLayout, range: &RangeInclusive<usize>) -> String {
+    let (start_row, start_col) = seat_position_from_id(layout, *range.start());
+    let (end_row, end_col) = seat_position_from_id(layout, *range.end());
+    if range.start() == range.end() {
+        format!("seat {} (row {} column {})", range.start(), start_row, start_col)
+    } else {
+        format!(
+            "seats {}-{} (row {} column {} to row {} column {})",
+            range.start(), range.end(), start_row, start_col, end_row, end_col
+        )
+    }
 }
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     let filename = &args[1];
 
+    // Row and column bits can optionally follow the filename for differently sized planes,
+    // and "map" prints the seat map and every gap
+    let show_map = args.iter().skip(2).any(|arg| arg == "map");
+    let bits: Vec<&str> = args
+        .iter()
+        .skip(2)
+        .map(String::as_str)
+        .filter(|&arg| arg != "map")
+        .collect();
+    let layout = match bits[..] {
+        [] => DEFAULT_LAYOUT,
+        [row_bits, col_bits] => SeatLayout::new(
+            row_bits.parse().expect("Row bits must be a number"),
+            col_bits.parse().expect("Column bits must be a number"),
+        ).unwrap_or_else(|e| panic!("Invalid layout: {}", e)),
+        _ => panic!("Usage: day05 <file> [row bits] [column bits] [map]"),
+    };
+
     let contents = fs::read_to_string(filename).expect("Error opening file");
-    println!("Part 1: {}", part1(contents.lines()));
-    println!("Part 2: {}", part2(contents.lines()));
+    let seat_ids = parse_seat_ids(&layout, contents.lines())
+        .unwrap_or_else(|e| panic!("Invalid boarding pass: {}", e));
+    let gaps = find_gaps(&layout, &seat_ids);
+
+    if show_map {
+        let map = render_seat_map(&layout, &seat_ids)
+            .unwrap_or_else(|e| panic!("Can't draw seat map: {}", e));
+        println!("{}\n", map);
+        if let Some(front) = &gaps.front {
+            println!("Missing from the front: {}", describe_range(&layout, front));
+        }
+        if let Some(back) = &gaps.back {
+            println!("Missing from the back: {}", describe_range(&layout, back));
+        }
+        for gap in gaps.gaps.iter() {
+            println!("Gap: {}", describe_range(&layout, gap));
+        }
+        println!("{} missing seats between taken seats\n", gaps.missing_seats().count());
+    }
+
+    println!("Part 1: {}", part1(&seat_ids));
+    match part2(&gaps) {
+        Some(my_seat) => println!(
+            "Part 2: {} ({})",
+            my_seat,
+            seat_string_from_id(&layout, my_seat).unwrap()
+        ),
+        None => println!("Part 2: no single empty seat, found {} gaps", gaps.gaps.len()),
+    }
 }
 
 #[cfg(test)]
@@ -87,15 +345,85 @@ mod tests {
 
     #[test]
     fn gets_position_from_string() {
-        assert_eq!(seat_position_from_string("BFFFBBFRRR"), (70, 7));
-        assert_eq!(seat_position_from_string("FFFBBBFRRR"), (14, 7));
-        assert_eq!(seat_position_from_string("BBFFBBFRLL"), (102, 4));
+        assert_eq!(seat_position_from_string(&DEFAULT_LAYOUT, "BFFFBBFRRR"), Ok((70, 7)));
+        assert_eq!(seat_position_from_string(&DEFAULT_LAYOUT, "FFFBBBFRRR"), Ok((14, 7)));
+        assert_eq!(seat_position_from_string(&DEFAULT_LAYOUT, "BBFFBBFRLL"), Ok((102, 4)));
     }
 
     #[test]
     fn seat_ids_convert_correctly() {
-        assert_eq!(seat_id_from_position((70, 7)), 567);
-        assert_eq!(seat_id_from_position((14, 7)), 119);
-        assert_eq!(seat_id_from_position((102, 4)), 820);
+        assert_eq!(seat_id_from_position(&DEFAULT_LAYOUT, (70, 7)), 567);
+        assert_eq!(seat_id_from_position(&DEFAULT_LAYOUT, (14, 7)), 119);
+        assert_eq!(seat_id_from_position(&DEFAULT_LAYOUT, (102, 4)), 820);
+    }
+
+    #[test]
+    fn encodes_seats() {
+        assert_eq!(seat_string_from_position(&DEFAULT_LAYOUT, (70, 7)), Ok("BFFFBBFRRR".to_string()));
+        assert_eq!(seat_string_from_id(&DEFAULT_LAYOUT, 820), Ok("BBFFBBFRLL".to_string()));
+        assert_eq!(
+            seat_string_from_position(&DEFAULT_LAYOUT, (128, 0)),
+            Err(PassError::SeatOutOfRange(128, 0))
+        );
+    }
+
+    #[test]
+    fn other_layouts_round_trip() {
+        let layout = SeatLayout { row_bits: 4, col_bits: 2 };
+        assert_eq!(seat_position_from_string(&layout, "BFFBRL"), Ok((9, 2)));
+        for id in 0..64 {
+            let pass = seat_string_from_id(&layout, id).unwrap();
+            let position = seat_position_from_string(&layout, &pass).unwrap();
+            assert_eq!(seat_id_from_position(&layout, position), id);
+        }
+
+        assert_eq!(SeatLayout::new(4, 2), Ok(layout));
+        assert_eq!(SeatLayout::new(60, 3), Ok(SeatLayout { row_bits: 60, col_bits: 3 }));
+        assert_eq!(SeatLayout::new(64, 0), Err(LayoutTooLarge(64, 0)));
+        assert_eq!(SeatLayout::new(32, 32), Err(LayoutTooLarge(32, 32)));
+        assert_eq!(SeatLayout::new(usize::MAX, 1), Err(LayoutTooLarge(usize::MAX, 1)));
+    }
+
+    #[test]
+    fn rejects_bad_passes() {
+        assert_eq!(
+            seat_position_from_string(&DEFAULT_LAYOUT, "BFFFBBFRR"),
+            Err(PassError::WrongLength(10, 9))
+        );
+        assert_eq!(
+            seat_position_from_string(&DEFAULT_LAYOUT, "BFFFBBRRRR"),
+            Err(PassError::UnexpectedChar('R', 6))
+        );
+    }
+
+    #[test]
+    fn finds_every_gap() {
+        let layout = SeatLayout { row_bits: 2, col_bits: 2 };
+        let gaps = find_gaps(&layout, &[2, 3, 5, 9, 10]);
+        assert_eq!(gaps, SeatGaps {
+            front: Some(0..=1),
+            back: Some(11..=15),
+            gaps: vec![4..=4, 6..=8],
+        });
+        assert_eq!(gaps.missing_seats().collect::<Vec<usize>>(), vec![4, 6, 7, 8]);
+        assert_eq!(part2(&gaps), Some(4));
+
+        let full = find_gaps(&layout, &(0..16).collect::<Vec<usize>>());
+        assert_eq!(full, SeatGaps::default());
+        assert_eq!(part2(&full), None);
+    }
+
+    #[test]
+    fn renders_seat_map() {
+        let layout = SeatLayout { row_bits: 2, col_bits: 2 };
+        assert_eq!(
+            render_seat_map(&layout, &[2, 3, 5, 9, 10]),
+            Ok("0 ..##\n1 .#..\n2 .##.\n3 ....".to_string())
+        );
+        assert!(render_seat_map(&SeatLayout { row_bits: 8, col_bits: 8 }, &[]).is_ok());
+        assert_eq!(
+            render_seat_map(&SeatLayout { row_bits: 60, col_bits: 3 }, &[]),
+            Err(MapTooLarge(1 << 63))
+        );
     }
 }
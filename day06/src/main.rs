@@ -1,36 +1,125 @@
-use std::collections::HashSet;
 use std::env;
 use std::fs;
+use std::ops::{BitAnd, BitOr};
+
+const QUESTIONS: usize = 26;
+
+/// A set of answered questions, one bit per question from a to z
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+struct Answers(u32);
+
+impl Answers {
+    /// Build a set from a survey line, ignoring anything that isn't a question
+    fn from_survey(survey: &str) -> Self {
+        Self(survey
+            .chars()
+            .filter(|c| c.is_ascii_lowercase())
+            .fold(0, |acc, c| acc | 1 << (c as u8 - b'a')))
+    }
+
+    fn contains(&self, question: char) -> bool {
+        question.is_ascii_lowercase() && self.0 >> (question as u8 - b'a') & 1 == 1
+    }
+
+    fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    fn questions(&self) -> impl Iterator<Item=char> + '_ {
+        (b'a'..=b'z').map(char::from).filter(move |&q| self.contains(q))
+    }
+}
+
+impl BitOr for Answers {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitAnd for Answers {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        Self(self.0 & rhs.0)
+    }
+}
+
+/// The answers of each member of a travel group
+struct Group {
+    members: Vec<Answers>,
+}
+
+impl Group {
+    fn from_str(group: &str) -> Self {
+        Self {
+            members: group
+                .lines()
+                .filter(|line| !line.is_empty())
+                .map(Answers::from_survey)
+                .collect(),
+        }
+    }
+
+    /// Questions anyone in the group answered
+    fn anyone(&self) -> Answers {
+        self.members.iter().fold(Answers::default(), |acc, &answers| acc | answers)
+    }
+
+    /// Questions everyone in the group answered
+    fn everyone(&self) -> Answers {
+        self.members
+            .iter()
+            .copied()
+            .reduce(|acc, answers| acc & answers)
+            .unwrap_or_default()
+    }
+
+    /// How many members answered each question, indexed from a
+    fn histogram(&self) -> [usize; QUESTIONS] {
+        let mut histogram = [0; QUESTIONS];
+        for answers in self.members.iter() {
+            for (question, count) in histogram.iter_mut().enumerate() {
+                *count += (answers.0 >> question & 1) as usize;
+            }
+        }
+        histogram
+    }
+
+    /// Questions answered by a number of members matching a predicate
+    fn answered_by(&self, predicate: impl Fn(usize) -> bool) -> Answers {
+        Answers(self
+            .histogram()
+            .iter()
+            .enumerate()
+            .filter(|(_, &count)| count > 0 && predicate(count))
+            .fold(0, |acc, (question, _)| acc | 1 << question))
+    }
+
+    /// Questions at least k members answered
+    fn at_least(&self, k: usize) -> Answers {
+        self.answered_by(|count| count >= k)
+    }
+
+    /// Questions exactly one member answered
+    fn exactly_one(&self) -> Answers {
+        self.answered_by(|count| count == 1)
+    }
+}
+
+fn parse_groups(responses: &str) -> Vec<Group> {
+    responses.split("\n\n").map(Group::from_str).collect()
+}
 
 /// Find the number of unique responses per group and sum them
-fn part1(responses: &String) -> usize {
-    responses.split("\n\n")
-        .fold(0, |acc, group| {
-            let answer_set: HashSet<_> = group.chars()
-                .filter(|c| c.is_alphabetic()).collect();
-            acc + answer_set.len()
-        })
+fn part1(responses: &str) -> usize {
+    parse_groups(responses).iter().map(|group| group.anyone().len()).sum()
 }
 
 /// Find the number of responses every member of a group marked
-fn part2(responses: &String) -> usize {
-    responses.split("\n\n")
-        .fold(0, |acc, group| {
-            group.lines()
-                .fold(None, |group_acc: Option<HashSet<char>>, survey| {
-                    let answer_set: HashSet<char> = survey.chars().collect();
-                    match group_acc {
-                        None => Some(answer_set),
-                        Some(acc_set) =>
-                            Some(
-                                acc_set
-                                    .intersection(&answer_set)
-                                    .cloned()
-                                    .collect()
-                            )
-                    }
-                }).map_or(0, |s| s.len()) + acc
-        })
+fn part2(responses: &str) -> usize {
+    parse_groups(responses).iter().map(|group| group.everyone().len()).sum()
 }
 
 fn main() {
@@ -40,9 +129,30 @@ fn main() {
     let contents = fs::read_to_string(filename).expect("Error opening file");
     println!("Part 1: {}", part1(&contents));
     println!("Part 2: {}", part2(&contents));
+
+    // Pass "histogram" after the filename for a breakdown of every group's answers
+    if args.get(2).map(String::as_str) == Some("histogram") {
+        for (index, group) in parse_groups(&contents).iter().enumerate() {
+            let histogram = group.histogram();
+            let counts: Vec<String> = group
+                .anyone()
+                .questions()
+                .map(|q| format!("{}:{}", q, histogram[(q as u8 - b'a') as usize]))
+                .collect();
+            println!(
+                "Group {} ({} members): {} | majority: {} | only one: {}",
+                index + 1,
+                group.members.len(),
+                counts.join(" "),
+                group.at_least(group.members.len().div_ceil(2)).questions().collect::<String>(),
+                group.exactly_one().questions().collect::<String>(),
+            );
+        }
+    }
 }
 
 #[cfg(test)]
+#[allow(clippy::unnecessary_to_owned)] // The examples pass owned input, from when parts took &String
 mod tests {
     use super::*;
 
@@ -57,4 +167,16 @@ mod tests {
     fn part2_example() {
         assert_eq!(part2(&SAMPLE.to_string()), 6);
     }
+
+    #[test]
+    fn group_queries() {
+        let group = Group::from_str("abc\nabd\nae\n");
+        assert_eq!(group.anyone(), Answers::from_survey("abcde"));
+        assert_eq!(group.everyone(), Answers::from_survey("a"));
+        assert_eq!(group.at_least(2), Answers::from_survey("ab"));
+        assert_eq!(group.at_least(3), group.everyone());
+        assert_eq!(group.exactly_one(), Answers::from_survey("cde"));
+        assert_eq!(&group.histogram()[..5], &[3, 2, 1, 1, 1]);
+        assert_eq!(group.exactly_one().questions().collect::<String>(), "cde");
+    }
 }
@@ -0,0 +1,44 @@
+use crate::graph::BagGraph;
+use std::collections::HashSet;
+
+/// Quote a color as a DOT identifier
+fn dot_id(color: &str) -> String {
+    format!("\"{}\"", color.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Write the containment graph in Graphviz DOT format, with quantities as edge labels.
+/// With a root color, only that color and the bags that can be inside it are included.
+pub fn to_dot(graph: &BagGraph, root: Option<&str>) -> String {
+    let included: Vec<&str> = match root {
+        Some(root) => {
+            let descendants = graph.descendants(root);
+            graph
+                .colors()
+                .iter()
+                .copied()
+                .filter(|&color| color == root || descendants.contains(color))
+                .collect()
+        }
+        None => graph.colors().to_vec(),
+    };
+    let included_set: HashSet<&str> = included.iter().copied().collect();
+
+    let mut dot = String::from("digraph bags {\n");
+    for color in included.iter() {
+        dot.push_str(&format!("    {};\n", dot_id(color)));
+    }
+    for color in included.iter() {
+        for child in graph.children(color) {
+            if included_set.contains(child.color) {
+                dot.push_str(&format!(
+                    "    {} -> {} [label=\"{}\"];\n",
+                    dot_id(color),
+                    dot_id(child.color),
+                    child.quantity
+                ));
+            }
+        }
+    }
+    dot.push('}');
+    dot
+}
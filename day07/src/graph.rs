@@ -0,0 +1,316 @@
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+
+const CONTAINED_RE_STR: &str = r"(?P<quantity>\d+) (?P<color>.+?) bag";
+const CONTAINER_RE_STR: &str = r"^(?P<color>.+?) bags";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BagRelationship<'a> {
+    pub color: &'a str,
+    pub quantity: usize,
+}
+
+pub type BagMap<'a> = HashMap<&'a str, Vec<BagRelationship<'a>>>;
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum BagError<'a> {
+    /// The colors in a containment cycle, starting and ending with the same color
+    Cycle(Vec<&'a str>),
+}
+
+impl<'a> Display for BagError<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BagError::Cycle(colors) => write!(f, "bags contain themselves: {}", colors.join(" -> ")),
+        }
+    }
+}
+
+/// A chain of bags, each inside the one before it
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BagPath<'a> {
+    pub colors: Vec<&'a str>,
+    /// How many of the last bag this chain puts in the first, the product of each step
+    pub quantity: usize,
+}
+
+/// If color is already on the search path in stack, get the cycle it closes
+fn cycle_on_stack<'a>(stack: &[&'a str], color: &'a str) -> Option<Vec<&'a str>> {
+    stack.iter().position(|&c| c == color).map(|start| {
+        let mut cycle = stack[start..].to_vec();
+        cycle.push(color);
+        cycle
+    })
+}
+
+/// Bag rules, indexed both from containers to their contents and from contents to containers
+pub struct BagGraph<'a> {
+    /// Every color mentioned by the rules, in the order first seen
+    colors: Vec<&'a str>,
+    children: BagMap<'a>,
+    parents: BagMap<'a>,
+}
+
+impl<'a> BagGraph<'a> {
+    /// Build the graph from rule lines
+    pub fn from_rules(specs: impl Iterator<Item=&'a str>) -> Self {
+        let contained_re = Regex::new(CONTAINED_RE_STR).unwrap();
+        let container_re = Regex::new(CONTAINER_RE_STR).unwrap();
+
+        let mut colors: Vec<&'a str> = Vec::new();
+        let mut seen: HashSet<&'a str> = HashSet::new();
+        let mut children: BagMap<'a> = HashMap::new();
+        let mut parents: BagMap<'a> = HashMap::new();
+
+        for spec in specs {
+            let parent = container_re
+                .captures(spec)
+                .and_then(|c| c.name("color"))
+                .unwrap()
+                .as_str();
+            if seen.insert(parent) {
+                colors.push(parent);
+            }
+
+            let contents = children.entry(parent).or_default();
+            for caps in contained_re.captures_iter(spec) {
+                let child = caps.name("color").unwrap().as_str();
+                let quantity: usize = caps
+                    .name("quantity")
+                    .and_then(|q| q.as_str().parse().ok())
+                    .unwrap();
+
+                if seen.insert(child) {
+                    colors.push(child);
+                }
+                contents.push(BagRelationship { color: child, quantity });
+                parents
+                    .entry(child)
+                    .or_default()
+                    .push(BagRelationship { color: parent, quantity });
+            }
+        }
+
+        Self { colors, children, parents }
+    }
+
+    /// Every color mentioned by the rules, in the order first seen
+    pub fn colors(&self) -> &[&'a str] {
+        &self.colors
+    }
+
+    /// Whether any rule mentions this color
+    pub fn contains(&self, color: &str) -> bool {
+        self.children.contains_key(color) || self.parents.contains_key(color)
+    }
+
+    /// The bags that directly go in a bag of this color
+    pub fn children(&self, color: &str) -> &[BagRelationship<'a>] {
+        self.children.get(color).map_or(&[], |children| &children[..])
+    }
+
+    /// The bags that can directly hold a bag of this color
+    pub fn parents(&self, color: &str) -> &[BagRelationship<'a>] {
+        self.parents.get(color).map_or(&[], |parents| &parents[..])
+    }
+
+    /// Get a set of the bags that can contain this bag
+    pub fn ancestors(&self, color: &str) -> HashSet<&'a str> {
+        let mut found: HashSet<&'a str> = HashSet::new();
+        let mut to_visit: Vec<&'a str> = self.parents(color).iter().map(|p| p.color).collect();
+
+        while let Some(ancestor) = to_visit.pop() {
+            if found.insert(ancestor) {
+                to_visit.extend(self.parents(ancestor).iter().map(|p| p.color));
+            }
+        }
+
+        found
+    }
+
+    /// Get a set of the bags that can be inside this bag
+    pub fn descendants(&self, color: &str) -> HashSet<&'a str> {
+        let mut found: HashSet<&'a str> = HashSet::new();
+        let mut to_visit: Vec<&'a str> = self.children(color).iter().map(|c| c.color).collect();
+
+        while let Some(descendant) = to_visit.pop() {
+            if found.insert(descendant) {
+                to_visit.extend(self.children(descendant).iter().map(|c| c.color));
+            }
+        }
+
+        found
+    }
+
+    /// Find a cycle of bags containing each other, if any exists
+    pub fn find_cycle(&self) -> Option<Vec<&'a str>> {
+        let mut finished: HashSet<&'a str> = HashSet::new();
+        self.colors.iter().find_map(|color|
+            self.cycle_from(color, &mut Vec::new(), &mut finished)
+        )
+    }
+
+    /// Depth first search for a cycle, with the current path kept in stack
+    fn cycle_from(
+        &self,
+        color: &'a str,
+        stack: &mut Vec<&'a str>,
+        finished: &mut HashSet<&'a str>,
+    ) -> Option<Vec<&'a str>> {
+        if let Some(cycle) = cycle_on_stack(stack, color) {
+            return Some(cycle);
+        }
+        if finished.contains(color) {
+            return None;
+        }
+
+        stack.push(color);
+        let cycle = self.children(color).iter().find_map(|child|
+            self.cycle_from(child.color, stack, finished)
+        );
+        stack.pop();
+        finished.insert(color);
+        cycle
+    }
+
+    /// Count the number of bags that have to be in a given bag color
+    pub fn contained_count(&self, color: &'a str) -> Result<usize, BagError<'a>> {
+        self.count_with_memo(color, &mut HashMap::new(), &mut Vec::new())
+    }
+
+    /// Count contained bags, storing results per color in memos and the current path in stack
+    fn count_with_memo(
+        &self,
+        color: &'a str,
+        memos: &mut HashMap<&'a str, usize>,
+        stack: &mut Vec<&'a str>,
+    ) -> Result<usize, BagError<'a>> {
+        if let Some(&memo) = memos.get(color) {
+            return Ok(memo);
+        }
+        if let Some(cycle) = cycle_on_stack(stack, color) {
+            return Err(BagError::Cycle(cycle));
+        }
+
+        stack.push(color);
+        let mut acc = 0;
+        for child in self.children(color) {
+            acc += child.quantity + child.quantity * self.count_with_memo(child.color, memos, stack)?;
+        }
+        stack.pop();
+
+        memos.insert(color, acc);
+        Ok(acc)
+    }
+
+    /// Find the longest chain of bags nested inside each other, starting with this color
+    pub fn deepest_chain(&self, color: &'a str) -> Result<Vec<&'a str>, BagError<'a>> {
+        self.chain_with_memo(color, &mut HashMap::new(), &mut Vec::new())
+    }
+
+    /// Find the deepest chain, storing results per color in memos and the current path in stack
+    fn chain_with_memo(
+        &self,
+        color: &'a str,
+        memos: &mut HashMap<&'a str, Vec<&'a str>>,
+        stack: &mut Vec<&'a str>,
+    ) -> Result<Vec<&'a str>, BagError<'a>> {
+        if let Some(memo) = memos.get(color) {
+            return Ok(memo.clone());
+        }
+        if let Some(cycle) = cycle_on_stack(stack, color) {
+            return Err(BagError::Cycle(cycle));
+        }
+
+        stack.push(color);
+        let mut deepest: Vec<&'a str> = Vec::new();
+        for child in self.children(color) {
+            let chain = self.chain_with_memo(child.color, memos, stack)?;
+            if chain.len() > deepest.len() {
+                deepest = chain;
+            }
+        }
+        stack.pop();
+
+        deepest.insert(0, color);
+        memos.insert(color, deepest.clone());
+        Ok(deepest)
+    }
+
+    /// Find every chain of bags from the outer color down to the inner one
+    pub fn paths(&self, outer: &'a str, inner: &'a str) -> Result<Vec<BagPath<'a>>, BagError<'a>> {
+        // Only bags that can hold the inner bag lead anywhere useful
+        let leads_to_inner = self.ancestors(inner);
+        let mut paths: Vec<BagPath<'a>> = Vec::new();
+        let mut stack = vec![outer];
+
+        if outer == inner && !leads_to_inner.contains(inner) {
+            return Ok(vec![BagPath { colors: stack, quantity: 1 }]);
+        }
+        self.paths_from(outer, 1, inner, &leads_to_inner, &mut stack, &mut paths)?;
+        Ok(paths)
+    }
+
+    /// Extend the path in stack toward inner, adding completed paths to paths
+    fn paths_from(
+        &self,
+        color: &'a str,
+        quantity: usize,
+        inner: &'a str,
+        leads_to_inner: &HashSet<&'a str>,
+        stack: &mut Vec<&'a str>,
+        paths: &mut Vec<BagPath<'a>>,
+    ) -> Result<(), BagError<'a>> {
+        for child in self.children(color) {
+            let child_quantity = quantity * child.quantity;
+            if child.color == inner {
+                let mut colors = stack.clone();
+                colors.push(inner);
+                paths.push(BagPath { colors, quantity: child_quantity });
+            }
+            // The inner bag itself only leads anywhere if it can contain itself
+            if leads_to_inner.contains(child.color) {
+                if let Some(cycle) = cycle_on_stack(stack, child.color) {
+                    return Err(BagError::Cycle(cycle));
+                }
+                stack.push(child.color);
+                self.paths_from(child.color, child_quantity, inner, leads_to_inner, stack, paths)?;
+                stack.pop();
+            }
+        }
+        Ok(())
+    }
+
+    /// Order every color so that bags come before any bag that can go inside them
+    pub fn topological_order(&self) -> Result<Vec<&'a str>, BagError<'a>> {
+        let mut parent_counts: HashMap<&'a str, usize> = self.colors
+            .iter()
+            .map(|&color| (color, self.parents(color).len()))
+            .collect();
+        let mut ready: Vec<&'a str> = self.colors
+            .iter()
+            .rev()
+            .copied()
+            .filter(|color| parent_counts[color] == 0)
+            .collect();
+        let mut order: Vec<&'a str> = Vec::new();
+
+        while let Some(color) = ready.pop() {
+            order.push(color);
+            for child in self.children(color).iter().rev() {
+                let count = parent_counts.get_mut(child.color).unwrap();
+                *count -= 1;
+                if *count == 0 {
+                    ready.push(child.color);
+                }
+            }
+        }
+
+        if order.len() < self.colors.len() {
+            Err(BagError::Cycle(self.find_cycle().unwrap()))
+        } else {
+            Ok(order)
+        }
+    }
+}
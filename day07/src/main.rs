@@ -1,118 +1,37 @@
-use regex::Regex;
-use std::collections::{HashMap, HashSet};
+mod dot;
+mod graph;
+mod query;
+
+use crate::dot::to_dot;
+use crate::graph::{BagError, BagGraph};
+use crate::query::BagQuery;
 use std::env;
 use std::fs;
+use std::io::{self, BufRead};
 
-const CONTAINED_RE_STR: &str = r"(?P<quantity>\d+) (?P<color>.+?) bag";
-const CONTAINER_RE_STR: &str = r"^(?P<color>.+?) bags";
-
-#[derive(Clone, Copy)]
-struct BagRelationship<'a> {
-    color: &'a str,
-    quantity: usize,
+fn part1(graph: &BagGraph) -> usize {
+    graph.ancestors("shiny gold").len()
 }
 
-type BagMap<'a> = HashMap<&'a str, Vec<BagRelationship<'a>>>;
-
-/// Get a map of bags to the bags containing them, with counts of how many of this bag go into
-/// them
-fn generate_child_parents_map<'a>(specs: impl Iterator<Item=&'a str>)
-    -> BagMap<'a> {
-    let contained_re = Regex::new(CONTAINED_RE_STR).unwrap();
-    let container_re = Regex::new(CONTAINER_RE_STR).unwrap();
-
-    let mut child_parents_map: BagMap<'a> = HashMap::new();
-
-    for spec in specs {
-        let parent = container_re
-            .captures(spec)
-            .and_then(|c| c.name("color"))
-            .unwrap()
-            .as_str();
-
-        for caps in contained_re.captures_iter(spec) {
-            let child = caps.name("color").unwrap().as_str();
-            let quantity: usize = caps
-                .name("quantity")
-                .and_then(|q| q.as_str().parse().ok())
-                .unwrap();
-
-            let relationship = BagRelationship {
-                    color: parent,
-                    quantity,
-            };
-            child_parents_map
-                .entry(child)
-                .and_modify(|e| e.push(relationship))
-                .or_insert(vec![relationship]);
-        }
-    }
-
-    child_parents_map
+fn part2<'a>(graph: &BagGraph<'a>) -> Result<usize, BagError<'a>> {
+    graph.contained_count("shiny gold")
 }
 
-/// Get a map of bags to the bags they contain and how many of each
-fn generate_parent_children_map<'a>(specs: impl Iterator<Item=&'a str>)
-    -> BagMap<'a> {
-    let contained_re = Regex::new(CONTAINED_RE_STR).unwrap();
-    let container_re = Regex::new(CONTAINER_RE_STR).unwrap();
-
-    specs.map(|spec| {
-        let parent = container_re
-            .captures(spec)
-            .and_then(|c| c.name("color"))
-            .unwrap()
-            .as_str();
-
-        let children: Vec<BagRelationship<'a>> = contained_re
-            .captures_iter(spec)
-            .map(|caps| BagRelationship {
-                color: caps.name("color").unwrap().as_str(),
-                quantity: caps
-                    .name("quantity")
-                    .and_then(|q| q.as_str().parse().ok())
-                    .unwrap()
-            }).collect();
-
-        (parent, children)
-    }).collect()
-}
-
-/// Get a set of the bags that can contain this bag
-fn ancestors<'a>(map: &BagMap<'a>, color: &'a str) -> HashSet<&'a str> {
-    match map.get(color) {
-        None => HashSet::new(),
-        Some(parents) => {
-            let parent_set: HashSet<&str> = parents
-                .iter()
-                .map(|p| p.color)
-                .collect();
-            parents.iter().fold(
-                parent_set,
-                |acc, parent| acc
-                    .union(&ancestors(map, parent.color))
-                    .cloned()
-                    .collect()
-            )
-        }
+/// Print everything known about a color
+fn print_query(graph: &BagGraph, color: &str) {
+    match BagQuery::new(graph, color) {
+        Some(query) => println!("{}", query),
+        None => println!("No rules mention {} bags", color),
     }
 }
 
-/// Count the number of bags that have to be in a given bag color
-fn child_count<'a>(map: &BagMap<'a>, color: &'a str) -> usize {
-    map.get(color).map(|children| {
-        children.iter().fold(0, |acc, child| {
-            acc + child.quantity + child.quantity * child_count(map, child.color)
-        })
-    }).unwrap_or_default() // 0 if color is not in map
-}
-
-fn part1(map: &BagMap) -> usize {
-    ancestors(map, "shiny gold").len()
-}
-
-fn part2(map: &BagMap) -> usize {
-    child_count(map, "shiny gold")
+/// Split the words of two colors given on the command line, quoted or not, at the point where
+/// both halves are colors in the rules
+fn split_colors(graph: &BagGraph, args: &[String]) -> Option<(String, String)> {
+    let words: Vec<&str> = args.iter().flat_map(|arg| arg.split_whitespace()).collect();
+    (1..words.len())
+        .map(|split| (words[..split].join(" "), words[split..].join(" ")))
+        .find(|(outer, inner)| graph.contains(outer) && graph.contains(inner))
 }
 
 fn main() {
@@ -120,35 +39,192 @@ fn main() {
     let filename = &args[1];
 
     let contents = fs::read_to_string(filename).expect("Error opening file");
-    let child_parent_map = generate_child_parents_map(contents.lines());
+    let graph = BagGraph::from_rules(contents.lines());
+
+    // Pass "dot" after the filename for a Graphviz graph of the rules, optionally followed by
+    // a color to limit the graph to the bags inside it
+    if args.get(2).map(String::as_str) == Some("dot") {
+        let root = args[3..].join(" ");
+        println!("{}", to_dot(&graph, if root.is_empty() { None } else { Some(&root) }));
+        return;
+    }
+
+    // Pass "paths" and two colors, like "paths shiny gold dark red", for every chain from the
+    // first down to the second
+    if args.get(2).map(String::as_str) == Some("paths") {
+        match split_colors(&graph, &args[3..]) {
+            Some((outer, inner)) => match graph.paths(&outer, &inner) {
+                Ok(paths) => {
+                    for path in paths.iter() {
+                        println!("{} x{}", path.colors.join(" > "), path.quantity);
+                    }
+                    println!("{} paths, {} bags in total", paths.len(),
+                             paths.iter().map(|path| path.quantity).sum::<usize>());
+                }
+                Err(e) => println!("{}", e),
+            },
+            None => println!("Usage: day07 <file> paths <outer color> <inner color>"),
+        }
+        return;
+    }
+
+    // Pass "order" for every color from outermost to innermost
+    if args.get(2).map(String::as_str) == Some("order") {
+        match graph.topological_order() {
+            Ok(order) => order.iter().for_each(|color| println!("{}", color)),
+            Err(e) => println!("{}", e),
+        }
+        return;
+    }
+
+    // Pass a color after the filename to query it, or "-" to query colors from stdin
+    let color = args[2..].join(" ");
+    if color == "-" {
+        for line in io::stdin().lock().lines() {
+            let line = line.expect("Error reading stdin");
+            if !line.trim().is_empty() {
+                print_query(&graph, line.trim());
+                println!();
+            }
+        }
+        return;
+    } else if !color.is_empty() {
+        print_query(&graph, &color);
+        return;
+    }
 
-    println!("Part 1: {}", part1(&child_parent_map));
+    if let Some(cycle) = graph.find_cycle() {
+        println!("Warning: {}", BagError::Cycle(cycle));
+    }
 
-    let parent_child_map = generate_parent_children_map(contents.lines());
-    println!("Part 2: {}", part2(&parent_child_map));
+    println!("Part 1: {}", part1(&graph));
+    match part2(&graph) {
+        Ok(count) => println!("Part 2: {}", count),
+        Err(e) => println!("Part 2: {}", e),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::graph::BagPath;
 
     const SAMPLE: &str = include_str!("sample");
 
+    #[test]
+    fn splits_path_colors() {
+        let graph = BagGraph::from_rules(SAMPLE.lines());
+        let args = |words: &[&str]| words.iter().map(|w| w.to_string()).collect::<Vec<String>>();
+
+        let expected = Some(("shiny gold".to_string(), "dark olive".to_string()));
+        assert_eq!(split_colors(&graph, &args(&["shiny", "gold", "dark", "olive"])), expected);
+        assert_eq!(split_colors(&graph, &args(&["shiny gold", "dark olive"])), expected);
+        assert_eq!(split_colors(&graph, &args(&["shiny", "gold"])), None);
+        assert_eq!(split_colors(&graph, &args(&[])), None);
+    }
+
     #[test]
     fn part1_example() {
-        let map = generate_child_parents_map(SAMPLE.lines());
-        assert_eq!(part1(&map), 4);
+        let graph = BagGraph::from_rules(SAMPLE.lines());
+        assert_eq!(part1(&graph), 4);
     }
 
     #[test]
     fn part2_example1() {
-        let map = generate_parent_children_map(SAMPLE.lines());
-        assert_eq!(part2(&map), 32);
+        let graph = BagGraph::from_rules(SAMPLE.lines());
+        assert_eq!(part2(&graph), Ok(32));
     }
 
     #[test]
     fn part2_example2() {
-        let map = generate_parent_children_map(include_str!("sample2").lines());
-        assert_eq!(part2(&map), 126)
+        let graph = BagGraph::from_rules(include_str!("sample2").lines());
+        assert_eq!(part2(&graph), Ok(126))
+    }
+
+    #[test]
+    fn detects_cycles() {
+        let rules = "shiny gold bags contain 1 dark red bag.\n\
+                     dark red bags contain 2 dark orange bags, 1 faded blue bag.\n\
+                     dark orange bags contain 3 shiny gold bags.\n\
+                     faded blue bags contain no other bags.";
+        let graph = BagGraph::from_rules(rules.lines());
+        let cycle = vec!["shiny gold", "dark red", "dark orange", "shiny gold"];
+
+        assert_eq!(graph.find_cycle(), Some(cycle.clone()));
+        assert_eq!(part2(&graph), Err(BagError::Cycle(cycle)));
+        assert_eq!(graph.contained_count("faded blue"), Ok(0));
+        assert_eq!(part1(&graph), 3); // Including itself
+        assert_eq!(BagGraph::from_rules(SAMPLE.lines()).find_cycle(), None);
+    }
+
+    #[test]
+    fn queries_any_color() {
+        let graph = BagGraph::from_rules(SAMPLE.lines());
+        let query = BagQuery::new(&graph, "muted yellow").unwrap();
+
+        assert_eq!(query.ancestors, vec!["dark orange", "light red"]);
+        assert_eq!(
+            query.descendants,
+            vec!["dark olive", "dotted black", "faded blue", "shiny gold", "vibrant plum"]
+        );
+        assert_eq!(query.contained_count, Ok(2 + 2 * 32 + 9));
+        assert_eq!(
+            query.deepest_chain,
+            Ok(vec!["muted yellow", "shiny gold", "dark olive", "faded blue"])
+        );
+        assert_eq!(query.rule.len(), 2);
+        assert!(query.to_string().starts_with(
+            "Rule: muted yellow bags contain 2 shiny gold bags, 9 faded blue bags.\n"
+        ));
+        assert_eq!(BagQuery::new(&graph, "plaid"), None);
+    }
+
+    #[test]
+    fn exports_dot() {
+        let graph = BagGraph::from_rules(SAMPLE.lines());
+        assert_eq!(
+            to_dot(&graph, Some("dark olive")),
+            "digraph bags {\n\
+             \x20   \"faded blue\";\n\
+             \x20   \"dark olive\";\n\
+             \x20   \"dotted black\";\n\
+             \x20   \"dark olive\" -> \"faded blue\" [label=\"3\"];\n\
+             \x20   \"dark olive\" -> \"dotted black\" [label=\"4\"];\n\
+             }"
+        );
+        assert_eq!(to_dot(&graph, None).matches(" -> ").count(), 13);
+    }
+
+    #[test]
+    fn enumerates_paths() {
+        let graph = BagGraph::from_rules(SAMPLE.lines());
+        let paths = graph.paths("shiny gold", "faded blue").unwrap();
+        assert_eq!(paths, vec![
+            BagPath { colors: vec!["shiny gold", "dark olive", "faded blue"], quantity: 3 },
+            BagPath { colors: vec!["shiny gold", "vibrant plum", "faded blue"], quantity: 10 },
+        ]);
+        assert_eq!(graph.paths("light red", "shiny gold").unwrap().len(), 2);
+        assert_eq!(graph.paths("faded blue", "shiny gold"), Ok(vec![]));
+    }
+
+    #[test]
+    fn orders_outermost_first() {
+        let graph = BagGraph::from_rules(SAMPLE.lines());
+        let order = graph.topological_order().unwrap();
+        let position = |color| order.iter().position(|&c| c == color).unwrap();
+
+        assert_eq!(order.len(), 9);
+        for &color in graph.colors() {
+            for child in graph.children(color) {
+                assert!(position(color) < position(child.color));
+            }
+        }
+
+        let cyclic = BagGraph::from_rules(
+            "a bags contain 1 b bag.\nb bags contain 1 a bag.\nc bags contain 1 a bag.".lines()
+        );
+        assert_eq!(cyclic.topological_order(), Err(BagError::Cycle(vec!["a", "b", "a"])));
+        assert_eq!(cyclic.paths("c", "b"), Err(BagError::Cycle(vec!["a", "b", "a"])));
+        assert_eq!(cyclic.paths("a", "a"), Err(BagError::Cycle(vec!["a", "b", "a"])));
     }
 }
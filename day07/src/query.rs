@@ -0,0 +1,73 @@
+use crate::graph::{BagError, BagGraph, BagRelationship};
+use std::fmt::Display;
+
+/// Everything the rules say about a single bag color
+#[derive(Debug, Eq, PartialEq)]
+pub struct BagQuery<'a> {
+    pub color: &'a str,
+    /// What goes directly inside this bag
+    pub rule: Vec<BagRelationship<'a>>,
+    /// Colors that can contain this bag, sorted
+    pub ancestors: Vec<&'a str>,
+    /// Colors that can be inside this bag, sorted
+    pub descendants: Vec<&'a str>,
+    pub contained_count: Result<usize, BagError<'a>>,
+    pub deepest_chain: Result<Vec<&'a str>, BagError<'a>>,
+}
+
+impl<'a> BagQuery<'a> {
+    /// Look up a color in the graph, or None if no rule mentions it
+    pub fn new(graph: &BagGraph<'a>, color: &'a str) -> Option<Self> {
+        if !graph.contains(color) {
+            return None;
+        }
+
+        let mut ancestors: Vec<&'a str> = graph.ancestors(color).into_iter().collect();
+        ancestors.sort_unstable();
+        let mut descendants: Vec<&'a str> = graph.descendants(color).into_iter().collect();
+        descendants.sort_unstable();
+
+        Some(Self {
+            color,
+            rule: graph.children(color).to_vec(),
+            ancestors,
+            descendants,
+            contained_count: graph.contained_count(color),
+            deepest_chain: graph.deepest_chain(color),
+        })
+    }
+}
+
+/// Write a rule back out the way it appears in the input
+fn describe_rule(color: &str, rule: &[BagRelationship]) -> String {
+    if rule.is_empty() {
+        return format!("{} bags contain no other bags.", color);
+    }
+
+    let contents: Vec<String> = rule
+        .iter()
+        .map(|child| format!(
+            "{} {} {}",
+            child.quantity,
+            child.color,
+            if child.quantity == 1 { "bag" } else { "bags" }
+        ))
+        .collect();
+    format!("{} bags contain {}.", color, contents.join(", "))
+}
+
+impl<'a> Display for BagQuery<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Rule: {}", describe_rule(self.color, &self.rule))?;
+        writeln!(f, "Ancestors ({}): {}", self.ancestors.len(), self.ancestors.join(", "))?;
+        writeln!(f, "Descendants ({}): {}", self.descendants.len(), self.descendants.join(", "))?;
+        match &self.contained_count {
+            Ok(count) => writeln!(f, "Total bags inside: {}", count)?,
+            Err(e) => writeln!(f, "Total bags inside: {}", e)?,
+        }
+        match &self.deepest_chain {
+            Ok(chain) => write!(f, "Deepest nesting ({}): {}", chain.len(), chain.join(" > ")),
+            Err(e) => write!(f, "Deepest nesting: {}", e),
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use crate::instruction::{parse_instruction, Instruction, Opcode, ParseError, Program};
+use std::collections::HashMap;
+use std::fmt::Display;
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum AssembleError {
+    Parse(ParseError),
+    /// Source line and the label that was never defined
+    UnknownLabel(usize, String),
+    /// Source line and the label defined a second time
+    DuplicateLabel(usize, String),
+    /// Source line and the label given to an opcode that doesn't jump
+    LabelNotAllowed(usize, String),
+}
+
+impl Display for AssembleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AssembleError::Parse(e) => write!(f, "{}", e),
+            AssembleError::UnknownLabel(line, label) =>
+                write!(f, "line {}: unknown label \"{}\"", line, label),
+            AssembleError::DuplicateLabel(line, label) =>
+                write!(f, "line {}: label \"{}\" is already defined", line, label),
+            AssembleError::LabelNotAllowed(line, label) =>
+                write!(f, "line {}: label \"{}\" used by an opcode that doesn't jump", line, label),
+        }
+    }
+}
+
+/// Remove a # or ; comment from a source line
+fn strip_comment(line: &str) -> &str {
+    line.find(['#', ';']).map_or(line, |start| &line[..start]).trim()
+}
+
+/// Split a leading "label:" from a source line
+fn split_label(line: &str) -> (Option<&str>, &str) {
+    match line.split_once(':') {
+        Some((label, rest)) if !label.is_empty() &&
+            label.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') =>
+            (Some(label), rest.trim()),
+        _ => (None, line),
+    }
+}
+
+/// Assemble boot code source, which may contain comments starting with # or ;, and labels
+/// ("name:") before instructions or on their own line. A label used as the argument of a jump
+/// becomes the relative offset from that instruction to the label.
+pub fn assemble(source: &str) -> Result<Program, AssembleError> {
+    // First pass: find where every label points and which lines hold instructions
+    let mut labels: HashMap<&str, usize> = HashMap::new();
+    let mut instruction_lines: Vec<(usize, &str)> = Vec::new();
+    for (source_line, text) in source.lines().enumerate() {
+        let (label, rest) = split_label(strip_comment(text));
+        if let Some(label) = label {
+            if labels.insert(label, instruction_lines.len()).is_some() {
+                return Err(AssembleError::DuplicateLabel(source_line, label.to_string()));
+            }
+        }
+        if !rest.is_empty() {
+            instruction_lines.push((source_line, rest));
+        }
+    }
+
+    // Second pass: resolve label arguments and parse
+    instruction_lines
+        .iter()
+        .enumerate()
+        .map(|(index, &(source_line, text))| {
+            let mut words = text.split_whitespace();
+            let opcode = words.next().unwrap_or_default();
+            let argument = words.next().unwrap_or_default();
+            let is_label = argument.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_');
+
+            let parsed = if is_label {
+                // Unknown opcodes fall through to parse_instruction's error
+                if Opcode::from_name(opcode).is_some_and(|opcode| !opcode.jumps()) {
+                    return Err(AssembleError::LabelNotAllowed(source_line, argument.to_string()));
+                }
+                let target = labels
+                    .get(argument)
+                    .ok_or_else(|| AssembleError::UnknownLabel(source_line, argument.to_string()))?;
+                let offset = *target as isize - index as isize;
+                parse_instruction(source_line, &format!("{} {:+}", opcode, offset))
+            } else {
+                parse_instruction(source_line, text)
+            };
+            parsed.map_err(AssembleError::Parse)
+        })
+        .collect()
+}
+
+/// Write a program as canonical source, one instruction per line
+pub fn write_program(program: &Program) -> String {
+    program
+        .iter()
+        .map(|instruction| format!("{}\n", instruction))
+        .collect()
+}
+
+/// The line a jump instruction can go to, even if that's the next line anyway
+fn jump_target(line: usize, instruction: &Instruction) -> Option<isize> {
+    if instruction.opcode.jumps() {
+        Some((line as isize).saturating_add(instruction.argument))
+    } else {
+        None
+    }
+}
+
+/// List the program with line numbers, marking where jumps go
+pub fn disassemble(program: &Program) -> String {
+    let width = program.len().to_string().len();
+
+    program
+        .iter()
+        .enumerate()
+        .map(|(line, instruction)| {
+            let text = format!("{:>width$}  {}", line, instruction, width = width);
+            match jump_target(line, instruction) {
+                Some(target) if target == program.len() as isize =>
+                    format!("{:<24}; -> end\n", text),
+                Some(target) if target < 0 || target > program.len() as isize =>
+                    format!("{:<24}; -> {} (out of bounds)\n", text, target),
+                Some(target) => format!("{:<24}; -> {}\n", text, target),
+                None => format!("{}\n", text),
+            }
+        })
+        .collect()
+}
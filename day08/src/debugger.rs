@@ -0,0 +1,203 @@
+use crate::instruction::Program;
+use crate::{ProgramError, ProgramState};
+use std::collections::{BTreeSet, HashSet};
+
+const HELP: &str = "\
+break N     stop before running line N
+delete N    remove the breakpoint on line N
+step [N]    run N instructions (default 1)
+continue    run until a breakpoint, watch, loop or the end of the program
+watch       stop whenever the accumulator changes
+unwatch     stop watching the accumulator
+print       show the current line and accumulator
+list        show the program around the current line
+reset       start the program over
+quit        leave the debugger";
+
+/// Why the debugger handed control back
+#[derive(Debug, Eq, PartialEq)]
+pub enum StopReason {
+    /// Ran the requested number of steps
+    Stepped,
+    Breakpoint(usize),
+    /// Old and new accumulator values
+    AccumulatorChanged(isize, isize),
+    /// A line about to run for the second time
+    LoopDetected(usize),
+    Finished(isize),
+    Error(ProgramError),
+}
+
+/// Steps through a program, gdb style
+pub struct Debugger<'a> {
+    program: &'a Program,
+    state: ProgramState<'a>,
+    breakpoints: BTreeSet<usize>,
+    watch_accumulator: bool,
+    /// Loop states seen since the last loop was reported, see ProgramState::loop_state
+    states_visited: HashSet<(usize, Option<isize>)>,
+    max_steps: usize,
+}
+
+impl<'a> Debugger<'a> {
+    /// A debugger whose runs fail after max_steps instructions, including after a reset
+    pub fn new(program: &'a Program, max_steps: usize) -> Self {
+        Self {
+            program,
+            state: ProgramState::with_step_limit(program, max_steps),
+            breakpoints: BTreeSet::new(),
+            watch_accumulator: false,
+            states_visited: HashSet::new(),
+            max_steps,
+        }
+    }
+
+    pub fn add_breakpoint(&mut self, line: usize) {
+        self.breakpoints.insert(line);
+    }
+
+    pub fn remove_breakpoint(&mut self, line: usize) -> bool {
+        self.breakpoints.remove(&line)
+    }
+
+    pub fn set_watch(&mut self, watch: bool) {
+        self.watch_accumulator = watch;
+    }
+
+    /// Start the program over, keeping breakpoints and watches
+    pub fn reset(&mut self) {
+        self.state = ProgramState::with_step_limit(self.program, self.max_steps);
+        self.states_visited.clear();
+    }
+
+    /// Run a single instruction, returning why execution should stop if it should
+    fn step_once(&mut self) -> Option<StopReason> {
+        let line = self.state.next_line;
+        if line == self.program.len() {
+            return Some(StopReason::Finished(self.state.accumulator));
+        }
+
+        let before = self.state.accumulator;
+        self.states_visited.insert(self.state.loop_state());
+        if let Err(e) = self.state.step() {
+            return Some(StopReason::Error(e));
+        }
+
+        let next_line = self.state.next_line;
+        if self.watch_accumulator && self.state.accumulator != before {
+            Some(StopReason::AccumulatorChanged(before, self.state.accumulator))
+        } else if self.breakpoints.contains(&next_line) {
+            Some(StopReason::Breakpoint(next_line))
+        } else if self.states_visited.contains(&self.state.loop_state()) {
+            // Forget the history so continuing again runs the loop once more
+            self.states_visited.clear();
+            Some(StopReason::LoopDetected(next_line))
+        } else {
+            None
+        }
+    }
+
+    /// Run up to count instructions, stopping early for breakpoints, watches and the end
+    pub fn step(&mut self, count: usize) -> StopReason {
+        for _ in 0..count {
+            if let Some(reason) = self.step_once() {
+                return reason;
+            }
+        }
+        StopReason::Stepped
+    }
+
+    /// Run until there's a reason to stop
+    pub fn run(&mut self) -> StopReason {
+        loop {
+            if let Some(reason) = self.step_once() {
+                break reason;
+            }
+        }
+    }
+
+    /// The current line, instruction and accumulator
+    pub fn describe(&self) -> String {
+        let line = self.state.next_line;
+        let instruction = self.program
+            .get(line)
+            .map_or("<end of program>".to_string(), |i| i.to_string());
+        format!(
+            "line {}: {}  acc = {}  steps = {}",
+            line, instruction, self.state.accumulator, self.state.steps
+        )
+    }
+
+    /// The program listing around the current line, marking it and any breakpoints
+    pub fn list(&self, context: usize) -> String {
+        let line = self.state.next_line;
+        let start = line.saturating_sub(context);
+        let end = line.saturating_add(context + 1).min(self.program.len());
+        (start..end)
+            .map(|l| format!(
+                "{}{} {:>4}  {}",
+                if l == line { '>' } else { ' ' },
+                if self.breakpoints.contains(&l) { '*' } else { ' ' },
+                l,
+                self.program[l]
+            ))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    fn describe_stop(&self, reason: StopReason) -> String {
+        let why = match reason {
+            StopReason::Stepped => String::new(),
+            StopReason::Breakpoint(line) => format!("Breakpoint at line {}\n", line),
+            StopReason::AccumulatorChanged(old, new) =>
+                format!("Accumulator changed from {} to {}\n", old, new),
+            StopReason::LoopDetected(line) => format!("Line {} is about to run again\n", line),
+            StopReason::Finished(acc) => return format!("Program finished with acc = {}", acc),
+            StopReason::Error(e) => return format!("Program error: {}", e),
+        };
+        format!("{}{}", why, self.describe())
+    }
+
+    /// Run a debugger command, returning what to show the user, or None to quit
+    pub fn command(&mut self, input: &str) -> Option<String> {
+        let mut words = input.split_whitespace();
+        let command = words.next().unwrap_or_default();
+        let number: Option<usize> = words.next().and_then(|n| n.parse().ok());
+
+        Some(match (command, number) {
+            ("b", Some(line)) | ("break", Some(line)) => {
+                self.add_breakpoint(line);
+                format!("Breakpoint set on line {}", line)
+            }
+            ("d", Some(line)) | ("delete", Some(line)) => if self.remove_breakpoint(line) {
+                format!("Breakpoint removed from line {}", line)
+            } else {
+                format!("No breakpoint on line {}", line)
+            },
+            ("s", count) | ("step", count) => {
+                let reason = self.step(count.unwrap_or(1));
+                self.describe_stop(reason)
+            }
+            ("c", _) | ("continue", _) => {
+                let reason = self.run();
+                self.describe_stop(reason)
+            }
+            ("watch", _) => {
+                self.set_watch(true);
+                "Watching the accumulator".to_string()
+            }
+            ("unwatch", _) => {
+                self.set_watch(false);
+                "No longer watching the accumulator".to_string()
+            }
+            ("p", _) | ("print", _) => self.describe(),
+            ("l", _) | ("list", _) => self.list(5),
+            ("reset", _) => {
+                self.reset();
+                self.describe()
+            }
+            ("q", _) | ("quit", _) => return None,
+            _ => HELP.to_string(),
+        })
+    }
+}
@@ -0,0 +1,172 @@
+use std::fmt::Display;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Opcode {
+    Acc,
+    Hlt,
+    Jmp,
+    /// Jump if the accumulator isn't zero
+    Jnz,
+    Nop,
+}
+
+/// Where execution goes after an instruction
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Flow {
+    /// On to the following line
+    Next,
+    /// Jump by an offset from the instruction's line
+    Jump(isize),
+    /// Stop the program
+    Halt,
+}
+
+/// The mnemonic and behaviour of an opcode
+struct OpcodeSpec {
+    opcode: Opcode,
+    name: &'static str,
+    /// Whether the argument is an offset to a line that may run next
+    jumps: bool,
+    /// Given the accumulator and argument, get the new accumulator and where to go next,
+    /// or None if the accumulator overflows
+    execute: fn(isize, isize) -> Option<(isize, Flow)>,
+    /// Given the argument, where execution goes next without running the program,
+    /// or None if that depends on the accumulator
+    static_flow: fn(isize) -> Option<Flow>,
+}
+
+/// The instruction set. A new opcode only needs a variant above and an entry here.
+const INSTRUCTION_SET: &[OpcodeSpec] = &[
+    OpcodeSpec {
+        opcode: Opcode::Acc,
+        name: "acc",
+        jumps: false,
+        execute: |acc, arg| Some((acc.checked_add(arg)?, Flow::Next)),
+        static_flow: |_| Some(Flow::Next),
+    },
+    OpcodeSpec {
+        opcode: Opcode::Hlt,
+        name: "hlt",
+        jumps: false,
+        execute: |acc, _| Some((acc, Flow::Halt)),
+        static_flow: |_| Some(Flow::Halt),
+    },
+    OpcodeSpec {
+        opcode: Opcode::Jmp,
+        name: "jmp",
+        jumps: true,
+        execute: |acc, arg| Some((acc, Flow::Jump(arg))),
+        static_flow: |arg| Some(Flow::Jump(arg)),
+    },
+    OpcodeSpec {
+        opcode: Opcode::Jnz,
+        name: "jnz",
+        jumps: true,
+        execute: |acc, arg| Some((acc, if acc != 0 { Flow::Jump(arg) } else { Flow::Next })),
+        static_flow: |_| None,
+    },
+    OpcodeSpec {
+        opcode: Opcode::Nop,
+        name: "nop",
+        jumps: false,
+        execute: |acc, _| Some((acc, Flow::Next)),
+        static_flow: |_| Some(Flow::Next),
+    },
+];
+
+impl Opcode {
+    fn spec(&self) -> &'static OpcodeSpec {
+        INSTRUCTION_SET
+            .iter()
+            .find(|spec| spec.opcode == *self)
+            .expect("Opcode missing from the instruction set")
+    }
+
+    /// Look up an opcode by its mnemonic
+    pub fn from_name(name: &str) -> Option<Self> {
+        INSTRUCTION_SET.iter().find(|spec| spec.name == name).map(|spec| spec.opcode)
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.spec().name
+    }
+
+    /// Run the opcode, returning the new accumulator and where to go next,
+    /// or None if the accumulator overflows
+    pub fn execute(&self, accumulator: isize, argument: isize) -> Option<(isize, Flow)> {
+        (self.spec().execute)(accumulator, argument)
+    }
+
+    /// Whether the argument is a jump offset, so it can be written as a label
+    pub fn jumps(&self) -> bool {
+        self.spec().jumps
+    }
+
+    /// Where the opcode sends execution for an argument, or None if it depends on the accumulator
+    pub fn static_flow(&self, argument: isize) -> Option<Flow> {
+        (self.spec().static_flow)(argument)
+    }
+}
+
+impl Display for Opcode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Instruction {
+    pub argument: isize,
+    pub opcode: Opcode,
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {:+}", self.opcode, self.argument)
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum ParseError {
+    /// Line number and the unrecognized opcode
+    UnknownOpcode(usize, String),
+    MissingArgument(usize),
+    /// Line number and the argument that isn't a number
+    InvalidArgument(usize, String),
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::UnknownOpcode(line, opcode) =>
+                write!(f, "line {}: unknown opcode \"{}\"", line, opcode),
+            ParseError::MissingArgument(line) => write!(f, "line {}: missing argument", line),
+            ParseError::InvalidArgument(line, argument) =>
+                write!(f, "line {}: invalid argument \"{}\"", line, argument),
+        }
+    }
+}
+
+pub type Program = Vec<Instruction>;
+
+/// Parse a single line of source as an Instruction
+pub fn parse_instruction(line: usize, instruction: &str) -> Result<Instruction, ParseError> {
+    let mut split = instruction.split_whitespace();
+    let name = split.next().unwrap_or_default();
+    let opcode = Opcode::from_name(name)
+        .ok_or_else(|| ParseError::UnknownOpcode(line, name.to_string()))?;
+    let argument_str = split.next().ok_or(ParseError::MissingArgument(line))?;
+    let argument = argument_str
+        .parse()
+        .map_err(|_| ParseError::InvalidArgument(line, argument_str.to_string()))?;
+
+    Ok(Instruction { argument, opcode })
+}
+
+/// Parse each item from a string slice iterator as an Instruction
+pub fn parse_program<'a>(instructions: impl Iterator<Item=&'a str>) -> Result<Program, ParseError> {
+    instructions
+        .enumerate()
+        .map(|(line, instruction)| parse_instruction(line, instruction))
+        .collect()
+}
@@ -1,135 +1,539 @@
+mod assembler;
+mod debugger;
+mod instruction;
+mod repair;
+mod trace;
+
+use crate::assembler::{assemble, disassemble, write_program};
+use crate::debugger::Debugger;
+use crate::instruction::{parse_program, Flow, Program};
+use crate::repair::find_fixes;
+use crate::trace::{first_divergence, Trace};
 use std::collections::HashSet;
 use std::env;
+use std::fmt::Display;
 use std::fs;
+use std::io::{self, BufRead, Write};
 
-#[derive(Copy, Clone, Debug)]
-struct Instruction<'a> {
-    argument: isize,
-    operation: &'a str,
-}
+/// Steps a program can run before it's assumed to never end
+const DEFAULT_MAX_STEPS: usize = 1_000_000;
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 enum ProgramError {
-    InfiniteLoop(isize, HashSet<usize>),
-    InvalidLine,
+    /// The accumulator when a line was about to run a second time
+    InfiniteLoop(isize),
+    /// Line of the jump and the line it tried to reach
+    JumpOutOfBounds(usize, isize),
+    /// Line of the instruction that overflowed the accumulator
+    AccumulatorOverflow(usize),
+    /// The step limit that was reached
+    StepLimitExceeded(usize),
 }
 
-type Program<'a> = Vec<Instruction<'a>>;
+impl Display for ProgramError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProgramError::InfiniteLoop(acc) => write!(f, "infinite loop with acc = {}", acc),
+            ProgramError::JumpOutOfBounds(line, target) =>
+                write!(f, "line {} jumps out of the program to line {}", line, target),
+            ProgramError::AccumulatorOverflow(line) =>
+                write!(f, "line {} overflows the accumulator", line),
+            ProgramError::StepLimitExceeded(limit) =>
+                write!(f, "program didn't end within {} steps", limit),
+        }
+    }
+}
 
 struct ProgramState<'a> {
     accumulator: isize,
     next_line: usize,
-    program: &'a Program<'a>,
+    program: &'a Program,
+    steps: usize,
+    max_steps: usize,
+    /// Whether every jump goes to the same place whatever the accumulator is
+    static_flow: bool,
 }
 
 impl<'a> ProgramState<'a> {
-    /// Create a new "run" of a given Program
-    fn new(program: &'a Program<'a>) -> ProgramState<'a> {
+    /// Create a new "run" that fails after running max_steps instructions
+    fn with_step_limit(program: &'a Program, max_steps: usize) -> ProgramState<'a> {
         ProgramState {
             accumulator: 0,
             next_line: 0,
             program,
+            steps: 0,
+            max_steps,
+            static_flow: program
+                .iter()
+                .all(|instruction| instruction.opcode.static_flow(instruction.argument).is_some()),
         }
     }
 
-    /// Steps forward in the program, returning true if next_line pointed to an instruction
-    fn step(&mut self) -> bool {
-        if let Some(instruction) = self.program.get(self.next_line) {
-            match instruction.operation {
-                "acc" => {
-                    self.accumulator += instruction.argument;
-                    self.next_line += 1;
-                }
-                "jmp" => {
-                    self.next_line = (self.next_line as isize + instruction.argument) as usize;
-                }
-                "nop" => { self.next_line += 1; }
-                _ => panic!("Unrecognized instruction: {}", instruction.operation)
-            };
-            true
-        } else { false }
+    /// What a run has to come back to for it to be stuck in a loop: the next line, plus the
+    /// accumulator if any jump depends on it (a countdown can revisit lines and still end)
+    fn loop_state(&self) -> (usize, Option<isize>) {
+        (self.next_line, if self.static_flow { None } else { Some(self.accumulator) })
     }
-}
 
-/// Parse each item from a string slice iterator as an Instruction
-fn parse_program<'a>(instructions: impl Iterator<Item=&'a str>) -> Vec<Instruction<'a>> {
-    instructions.map(|instruction| {
-        let mut split = instruction.split(' ');
-        let operation = split.next().unwrap();
-        let argument_str = split.next().unwrap();
-        Instruction {
-            argument: argument_str.parse().unwrap(),
-            operation,
+    /// Steps forward in the program, returning false if the program has already ended
+    fn step(&mut self) -> Result<bool, ProgramError> {
+        let instruction = match self.program.get(self.next_line) {
+            Some(instruction) => instruction,
+            None => return Ok(false),
+        };
+        if self.steps >= self.max_steps {
+            return Err(ProgramError::StepLimitExceeded(self.max_steps));
+        }
+
+        let (accumulator, flow) = instruction.opcode
+            .execute(self.accumulator, instruction.argument)
+            .ok_or(ProgramError::AccumulatorOverflow(self.next_line))?;
+        let target = match flow {
+            Flow::Next => self.next_line as isize + 1,
+            Flow::Jump(offset) => (self.next_line as isize).checked_add(offset)
+                .ok_or(ProgramError::JumpOutOfBounds(self.next_line, isize::MAX))?,
+            // Running off the end is how a program finishes
+            Flow::Halt => self.program.len() as isize,
+        };
+        if target < 0 || target as usize > self.program.len() {
+            return Err(ProgramError::JumpOutOfBounds(self.next_line, target));
         }
-    }).collect()
+
+        self.accumulator = accumulator;
+        self.next_line = target as usize;
+        self.steps += 1;
+        Ok(true)
+    }
 }
 
-/// Runs a program, returning an error and the lines run if a loop is detected
-fn run_program(program: &Program) -> Result<isize, ProgramError> {
-    let mut state = ProgramState::new(program);
-    let mut lines_visited: HashSet<usize> = HashSet::new();
+/// Runs a program, failing if it loops, misbehaves or runs more than max_steps instructions
+fn run_program_with_limit(program: &Program, max_steps: usize) -> Result<isize, ProgramError> {
+    let mut state = ProgramState::with_step_limit(program, max_steps);
+    let mut states_visited: HashSet<(usize, Option<isize>)> = HashSet::new();
 
     loop {
-        let visited = !lines_visited.insert(state.next_line);
+        let visited = !states_visited.insert(state.loop_state());
         if visited {
-            break Err(ProgramError::InfiniteLoop(state.accumulator, lines_visited));
+            break Err(ProgramError::InfiniteLoop(state.accumulator));
         }
-        // End of program?
-        if state.next_line == program.len() { break Ok(state.accumulator); }
-        if !state.step() {
-            break Err(ProgramError::InvalidLine);
+        if !state.step()? {
+            break Ok(state.accumulator);
         }
     }
 }
 
-fn part1(program: &Program) -> (isize, HashSet<usize>) {
-    match run_program(program) {
-        Err(ProgramError::InfiniteLoop(acc, lines)) => (acc, lines),
-        Err(e) => panic!("Unexpected program error {:?}", e),
+fn part1(program: &Program, max_steps: usize) -> isize {
+    match run_program_with_limit(program, max_steps) {
+        Err(ProgramError::InfiniteLoop(acc)) => acc,
+        Err(e) => panic!("Unexpected program error: {}", e),
         Ok(_) => panic!("Should have found infinite loop"),
     }
 }
 
-fn part2(program: &Program, candidates: HashSet<usize>) -> isize {
-    candidates.iter().find_map(|candidate| {
-        let candidate_instruction = program.get(*candidate).unwrap();
-        if candidate_instruction.operation == "acc" {
-            return None
-        } else {
-            let mut program_fix = program.clone();
-            let mut instruction = program_fix.get_mut(*candidate).unwrap();
-            match instruction.operation {
-                "jmp" => { instruction.operation = "nop"; }
-                "nop" => { instruction.operation = "jmp"; }
-                _ => panic!("Invalid candidate {:?}", instruction)
-            };
-            run_program(&program_fix).ok()
+/// Find the accumulator after fixing the one corrupted instruction
+fn part2(program: &Program, max_steps: usize) -> isize {
+    let fixes = find_fixes(program, max_steps)
+        .unwrap_or_else(|e| panic!("Can't repair program: {}", e));
+    fixes.first().expect("No single change fixes the program").accumulator
+}
+
+/// Remove "--max-steps N" from the arguments, returning N or the default if it wasn't given
+fn take_max_steps(args: &mut Vec<String>) -> usize {
+    match args.iter().position(|arg| arg == "--max-steps") {
+        Some(index) => {
+            let max_steps = args.get(index + 1)
+                .and_then(|steps| steps.parse().ok())
+                .expect("--max-steps needs a number of steps");
+            args.drain(index..=index + 1);
+            max_steps
         }
-    }).unwrap()
+        None => DEFAULT_MAX_STEPS,
+    }
 }
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+    // Pass "--max-steps N" anywhere to change how long a run can go before it's given up on
+    let max_steps = take_max_steps(&mut args);
     let filename = &args[1];
 
     let contents = fs::read_to_string(filename).expect("Error opening file");
-    let program = parse_program(contents.lines());
-    let (result, lines) = part1(&program);
-    println!("Part 1: {}", result);
-    println!("Part 2: {}", part2(&program, lines));
+    // .asm files may use comments and labels
+    let program = if filename.ends_with(".asm") {
+        assemble(&contents).unwrap_or_else(|e| panic!("Invalid program: {}", e))
+    } else {
+        parse_program(contents.lines()).unwrap_or_else(|e| panic!("Invalid program: {}", e))
+    };
+
+    // Pass "disasm" for a listing with line numbers and jump targets, or "canonical" to write
+    // the program back out as plain boot code
+    match args.get(2).map(String::as_str) {
+        Some("disasm") => {
+            print!("{}", disassemble(&program));
+            return;
+        }
+        Some("canonical") => {
+            print!("{}", write_program(&program));
+            return;
+        }
+        _ => {}
+    }
+
+    // Pass "fixes" after the filename to list every change that makes the program terminate
+    if args.get(2).map(String::as_str) == Some("fixes") {
+        if let Ok(acc) = run_program_with_limit(&program, max_steps) {
+            println!("The program already ends (acc = {})", acc);
+            return;
+        }
+        match find_fixes(&program, max_steps) {
+            Ok(fixes) if fixes.is_empty() => println!("No single change fixes the program"),
+            Ok(fixes) => for fix in fixes {
+                println!("line {}: {} -> {} (acc = {})",
+                         fix.line, program[fix.line], fix.instruction, fix.accumulator);
+            },
+            Err(e) => println!("Can't repair program: {}", e),
+        }
+        return;
+    }
+
+    // Pass "debug" after the filename to step through the program instead
+    if args.get(2).map(String::as_str) == Some("debug") {
+        let mut debugger = Debugger::new(&program, max_steps);
+        println!("{}", debugger.describe());
+        print!("(debug) ");
+        io::stdout().flush().unwrap();
+        for line in io::stdin().lock().lines() {
+            match debugger.command(&line.expect("Error reading stdin")) {
+                Some(output) => println!("{}", output),
+                None => break,
+            }
+            print!("(debug) ");
+            io::stdout().flush().unwrap();
+        }
+        return;
+    }
+
+    // Pass "trace" and a path to record this run, "replay" and a path to print a recorded run,
+    // or "diff" and a path to compare this run against a recorded one
+    if let (Some(mode), Some(path)) = (args.get(2), args.get(3)) {
+        let read_trace = || Trace::from_file_string(
+            &fs::read_to_string(path).expect("Error opening trace")
+        ).unwrap_or_else(|e| panic!("Invalid trace: {}", e));
+
+        match mode.as_str() {
+            "trace" => {
+                fs::write(path, Trace::record(&program, max_steps).to_file_string())
+                    .expect("Error writing trace");
+                return;
+            }
+            "replay" => {
+                let trace = read_trace();
+                for (index, step) in trace.steps.iter().enumerate() {
+                    let changed = program.get(step.line) != Some(&step.instruction);
+                    let note = if changed { "  (program differs)" } else { "" };
+                    println!("{:>6}: {}{}", index, step, note);
+                }
+                println!("{}", trace.end);
+                return;
+            }
+            "diff" => {
+                let recorded = read_trace();
+                let current = Trace::record(&program, max_steps);
+                match first_divergence(&recorded, &current) {
+                    Some(divergence) => println!(
+                        "Runs diverge at {}\nRecorded: {}\nCurrent: {}",
+                        divergence, recorded.end, current.end
+                    ),
+                    None => println!("Runs are identical"),
+                }
+                return;
+            }
+            _ => {}
+        }
+    }
+
+    println!("Part 1: {}", part1(&program, max_steps));
+    println!("Part 2: {}", part2(&program, max_steps));
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::instruction::Opcode;
 
     const SAMPLE: &str = include_str!("sample");
 
+    fn run_program(program: &Program) -> Result<isize, ProgramError> {
+        run_program_with_limit(program, DEFAULT_MAX_STEPS)
+    }
+
     #[test]
     fn example() {
-        let program = parse_program(SAMPLE.lines());
-        let (result, lines) = part1(&program);
-        assert_eq!(result, 5);
-        assert_eq!(part2(&program, lines), 8);
+        let program = parse_program(SAMPLE.lines()).unwrap();
+        assert_eq!(part1(&program, DEFAULT_MAX_STEPS), 5);
+        assert_eq!(part2(&program, DEFAULT_MAX_STEPS), 8);
+    }
+
+    #[test]
+    fn debugger() {
+        use crate::debugger::StopReason;
+
+        let program = parse_program(SAMPLE.lines()).unwrap();
+        let mut debugger = Debugger::new(&program, DEFAULT_MAX_STEPS);
+
+        debugger.add_breakpoint(3);
+        assert_eq!(debugger.run(), StopReason::Breakpoint(3));
+        assert_eq!(debugger.describe(), "line 3: acc +3  acc = 2  steps = 5");
+        assert_eq!(debugger.step(1), StopReason::Stepped);
+        assert_eq!(debugger.run(), StopReason::LoopDetected(1));
+
+        debugger.reset();
+        debugger.set_watch(true);
+        assert_eq!(debugger.run(), StopReason::AccumulatorChanged(0, 1));
+        assert!(debugger.remove_breakpoint(3));
+        assert_eq!(
+            debugger.command("step 2"),
+            Some("Accumulator changed from 1 to 2\nline 7: jmp -4  acc = 2  steps = 4".to_string())
+        );
+        assert_eq!(debugger.command("quit"), None);
+
+        let mut fixed = program.clone();
+        fixed[7].opcode = Opcode::Nop;
+        let mut debugger = Debugger::new(&fixed, DEFAULT_MAX_STEPS);
+        debugger.command("b 8");
+        assert_eq!(
+            debugger.command("c"),
+            Some("Breakpoint at line 8\nline 8: acc +6  acc = 2  steps = 5".to_string())
+        );
+        assert_eq!(debugger.run(), StopReason::Finished(8));
+    }
+
+    #[test]
+    fn trace_round_trip() {
+        use crate::trace::{TraceEnd, TraceParseError, TraceStep};
+
+        let program = parse_program(SAMPLE.lines()).unwrap();
+        let trace = Trace::record(&program, DEFAULT_MAX_STEPS);
+        assert_eq!(trace.steps.len(), 7);
+        assert_eq!(trace.steps[1], TraceStep {
+            line: 1,
+            instruction: program[1],
+            acc_before: 0,
+            acc_after: 1,
+        });
+        assert_eq!(trace.end, TraceEnd::Loop(1));
+
+        let contents = trace.to_file_string();
+        assert!(contents.starts_with("0 nop +0 0 0\n1 acc +1 0 1\n"));
+        assert_eq!(Trace::from_file_string(&contents), Ok(trace.clone()));
+        assert!(Trace::from_file_string("0 nop +0 0 0\n").is_err());
+        assert_eq!(
+            Trace::from_file_string("end loop -1"),
+            Err(TraceParseError(0, "end loop -1".to_string()))
+        );
+
+        let mut fixed = program.clone();
+        fixed[7].opcode = Opcode::Nop;
+        let fixed_trace = Trace::record(&fixed, DEFAULT_MAX_STEPS);
+        assert_eq!(fixed_trace.end, TraceEnd::Finished(8));
+
+        let divergence = first_divergence(&trace, &fixed_trace).unwrap();
+        assert_eq!(divergence.step, 4);
+        assert_eq!(divergence.right.unwrap().instruction.opcode, Opcode::Nop);
+        assert_eq!(first_divergence(&trace, &trace), None);
+
+        let overflow = parse_program("acc +9223372036854775807\nacc +1".lines()).unwrap();
+        let overflow_trace = Trace::record(&overflow, DEFAULT_MAX_STEPS);
+        assert_eq!(overflow_trace.end, TraceEnd::Error(ProgramError::AccumulatorOverflow(1)));
+        assert_eq!(
+            Trace::from_file_string(&overflow_trace.to_file_string()),
+            Ok(overflow_trace)
+        );
+    }
+
+    #[test]
+    fn finds_every_fix() {
+        use crate::repair::{ControlFlow, Fix, RepairError};
+        use crate::instruction::Instruction;
+
+        let program = parse_program(SAMPLE.lines()).unwrap();
+        let flow = ControlFlow::new(&program);
+        assert!(flow.terminates_from(8));
+        assert!(!flow.terminates_from(0));
+        assert_eq!(find_fixes(&program, DEFAULT_MAX_STEPS), Ok(vec![Fix {
+            line: 7,
+            instruction: Instruction { argument: -4, opcode: Opcode::Nop },
+            accumulator: 8,
+        }]));
+        // The fixed sample takes 6 steps
+        assert_eq!(find_fixes(&program, 5), Ok(Vec::new()));
+
+        // Either of the first two lines can skip the loop
+        let program = parse_program("nop +3\njmp +0\nacc +1".lines()).unwrap();
+        let fixes: Vec<usize> =
+            find_fixes(&program, DEFAULT_MAX_STEPS).unwrap().iter().map(|fix| fix.line).collect();
+        assert_eq!(fixes, vec![0, 1]);
+
+        // Conditional jumps have no static successor
+        let program = parse_program("acc +1\njnz +2\njmp -2\nnop +0".lines()).unwrap();
+        assert_eq!(run_program(&program), Ok(1));
+        assert_eq!(find_fixes(&program, DEFAULT_MAX_STEPS), Err(RepairError::DynamicFlow(1)));
+
+        // Conditional jumps the run never reaches don't matter...
+        let program = parse_program("jmp +0\njmp +2\njnz +0\nacc +1".lines()).unwrap();
+        assert_eq!(part2(&program, DEFAULT_MAX_STEPS), 1);
+
+        // ...and a flip that leads into one is run to see whether it ends
+        let program = parse_program("nop +0\njmp -1\njnz +1\nacc +1".lines()).unwrap();
+        let fixes: Vec<usize> =
+            find_fixes(&program, DEFAULT_MAX_STEPS).unwrap().iter().map(|fix| fix.line).collect();
+        assert_eq!(fixes, vec![1]);
+
+        // Both flips reach the end, but only by overflowing the accumulator
+        let program =
+            parse_program("acc +9223372036854775807\nnop +2\njmp -1\nacc +1".lines()).unwrap();
+        assert_eq!(find_fixes(&program, DEFAULT_MAX_STEPS), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn runtime_errors() {
+        let program = parse_program("acc +9223372036854775807\nacc +1".lines()).unwrap();
+        assert_eq!(run_program(&program), Err(ProgramError::AccumulatorOverflow(1)));
+
+        let program = parse_program("nop +0\njmp -5".lines()).unwrap();
+        assert_eq!(run_program(&program), Err(ProgramError::JumpOutOfBounds(1, -4)));
+
+        let program = parse_program("jmp +4\nnop +0\nnop +0".lines()).unwrap();
+        assert_eq!(run_program(&program), Err(ProgramError::JumpOutOfBounds(0, 4)));
+
+        // Jumping to just past the last line ends the program
+        let program = parse_program("jmp +2\nnop +0".lines()).unwrap();
+        assert_eq!(run_program(&program), Ok(0));
+
+        let program = parse_program("acc +1\nacc +1\nacc +1\nacc +1".lines()).unwrap();
+        assert_eq!(run_program_with_limit(&program, 3), Err(ProgramError::StepLimitExceeded(3)));
+        assert_eq!(run_program_with_limit(&program, 4), Ok(4));
+    }
+
+    #[test]
+    fn halts() {
+        let program = parse_program("acc +2\nhlt +0\nacc +5\njmp -3".lines()).unwrap();
+        assert_eq!(program[1].opcode, Opcode::Hlt);
+        assert_eq!(run_program(&program), Ok(2));
+        assert_eq!(
+            Trace::record(&program, DEFAULT_MAX_STEPS).end,
+            crate::trace::TraceEnd::Finished(2)
+        );
+    }
+
+    #[test]
+    fn counts_down() {
+        use crate::debugger::StopReason;
+
+        // Lines 1 and 2 run three times, but with a different accumulator each time
+        let program = parse_program("acc +3\nacc -1\njnz -1\nnop +0".lines()).unwrap();
+        assert_eq!(run_program(&program), Ok(0));
+        let trace = Trace::record(&program, DEFAULT_MAX_STEPS);
+        assert_eq!(trace.steps.len(), 8);
+        assert_eq!(trace.end, crate::trace::TraceEnd::Finished(0));
+        assert_eq!(Debugger::new(&program, DEFAULT_MAX_STEPS).run(), StopReason::Finished(0));
+
+        // Going round with the same accumulator is still a loop
+        let program = parse_program("acc +1\njnz +0".lines()).unwrap();
+        assert_eq!(run_program(&program), Err(ProgramError::InfiniteLoop(1)));
+    }
+
+    #[test]
+    fn step_limits() {
+        use crate::debugger::StopReason;
+        use crate::trace::TraceEnd;
+
+        let mut args: Vec<String> = ["day08", "--max-steps", "2", "input", "debug"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        assert_eq!(take_max_steps(&mut args), 2);
+        assert_eq!(args, vec!["day08", "input", "debug"]);
+        assert_eq!(take_max_steps(&mut args), DEFAULT_MAX_STEPS);
+
+        // The accumulator never comes back round, so only the limit stops this
+        let program = parse_program("acc +1\njnz -1".lines()).unwrap();
+        let exceeded = ProgramError::StepLimitExceeded(100);
+        assert_eq!(run_program_with_limit(&program, 100), Err(exceeded));
+        assert_eq!(Trace::record(&program, 100).end, TraceEnd::Error(exceeded));
+
+        let mut debugger = Debugger::new(&program, 100);
+        assert_eq!(debugger.run(), StopReason::Error(exceeded));
+        debugger.reset();
+        assert_eq!(debugger.run(), StopReason::Error(exceeded));
+    }
+
+    #[test]
+    fn assembles_labels_and_comments() {
+        use crate::assembler::AssembleError;
+
+        let source = "\
+            # The sample, with labels\n\
+            nop +0\n\
+            again: acc +1   ; back here from the end\n\
+            jmp skip\n\
+            top:\n\
+            acc +3\n\
+            jmp again\n\
+            acc -99\n\
+            skip: acc +1\n\
+            jmp top\n\
+            acc +6\n";
+        let program = assemble(source).unwrap();
+        assert_eq!(program, parse_program(SAMPLE.lines()).unwrap());
+        assert_eq!(write_program(&program), format!("{}\n", SAMPLE.trim_end()));
+        assert_eq!(assemble(&write_program(&program)).unwrap(), program);
+
+        assert_eq!(
+            assemble("jmp nowhere").unwrap_err(),
+            AssembleError::UnknownLabel(0, "nowhere".to_string())
+        );
+        assert_eq!(
+            assemble("a: nop +0\na: nop +0").unwrap_err(),
+            AssembleError::DuplicateLabel(1, "a".to_string())
+        );
+        assert_eq!(
+            assemble("loop: acc loop").unwrap_err(),
+            AssembleError::LabelNotAllowed(0, "loop".to_string())
+        );
+        assert_eq!(
+            assemble("acc +1\njnz end\nend: hlt +0").unwrap(),
+            parse_program("acc +1\njnz +1\nhlt +0".lines()).unwrap()
+        );
+    }
+
+    #[test]
+    fn disassembles() {
+        let program =
+            parse_program("nop +0\njmp +2\njmp +1\njmp -5\njnz +1".lines()).unwrap();
+        assert_eq!(
+            disassemble(&program),
+            "0  nop +0\n\
+             1  jmp +2               ; -> 3\n\
+             2  jmp +1               ; -> 3\n\
+             3  jmp -5               ; -> -2 (out of bounds)\n\
+             4  jnz +1               ; -> end\n"
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        use crate::instruction::ParseError;
+
+        assert_eq!(
+            parse_program("nop +0\nmul +2".lines()),
+            Err(ParseError::UnknownOpcode(1, "mul".to_string()))
+        );
+        assert_eq!(parse_program("jmp".lines()), Err(ParseError::MissingArgument(0)));
+        assert_eq!(
+            parse_program("acc 1x".lines()),
+            Err(ParseError::InvalidArgument(0, "1x".to_string()))
+        );
+        assert_eq!(parse_program(SAMPLE.lines()).unwrap()[4].to_string(), "jmp -3");
     }
 }
@@ -0,0 +1,153 @@
+use crate::instruction::{Flow, Instruction, Opcode, Program};
+use crate::run_program_with_limit;
+use std::collections::HashSet;
+use std::fmt::Display;
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum RepairError {
+    /// Line of an instruction whose next line depends on the accumulator
+    DynamicFlow(usize),
+}
+
+impl Display for RepairError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RepairError::DynamicFlow(line) =>
+                write!(f, "line {}: where it goes next depends on the accumulator", line),
+        }
+    }
+}
+
+/// The line an instruction on line would run next, Ok(None) if it jumps out of the program.
+/// Line program.len() is the end of the program.
+fn successor(
+    program: &Program,
+    line: usize,
+    instruction: &Instruction,
+) -> Result<Option<usize>, RepairError> {
+    let flow = instruction.opcode
+        .static_flow(instruction.argument)
+        .ok_or(RepairError::DynamicFlow(line))?;
+    let next = match flow {
+        Flow::Next => Some(line as isize + 1),
+        Flow::Jump(offset) => (line as isize).checked_add(offset),
+        Flow::Halt => Some(program.len() as isize),
+    };
+    Ok(next.filter(|&next| next >= 0 && next as usize <= program.len()).map(|next| next as usize))
+}
+
+/// The opcode an instruction could have been corrupted from
+fn flipped(opcode: Opcode) -> Option<Opcode> {
+    match opcode {
+        Opcode::Jmp => Some(Opcode::Nop),
+        Opcode::Nop => Some(Opcode::Jmp),
+        _ => None,
+    }
+}
+
+/// Lines marked true are the targets and every line whose static path leads to one of them
+fn reaching(predecessors: &[Vec<usize>], targets: Vec<usize>) -> Vec<bool> {
+    let mut reaches = vec![false; predecessors.len()];
+    for &target in targets.iter() {
+        reaches[target] = true;
+    }
+    let mut to_visit = targets;
+    while let Some(line) = to_visit.pop() {
+        for &predecessor in predecessors[line].iter() {
+            if !reaches[predecessor] {
+                reaches[predecessor] = true;
+                to_visit.push(predecessor);
+            }
+        }
+    }
+    reaches
+}
+
+/// Static control flow of a program. Lines whose next line depends on the accumulator have no
+/// known successor, so nothing is known about where running them leads.
+pub struct ControlFlow {
+    /// Lines that lead to the end of the program, indexed by line (including the end itself)
+    terminates: Vec<bool>,
+    /// Lines that lead to a line whose next line depends on the accumulator
+    reaches_dynamic: Vec<bool>,
+}
+
+impl ControlFlow {
+    pub fn new(program: &Program) -> Self {
+        let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); program.len() + 1];
+        let mut dynamic: Vec<usize> = Vec::new();
+        for (line, instruction) in program.iter().enumerate() {
+            match successor(program, line, instruction) {
+                Ok(Some(next)) => predecessors[next].push(line),
+                Ok(None) => {}
+                Err(_) => dynamic.push(line),
+            }
+        }
+
+        Self {
+            terminates: reaching(&predecessors, vec![program.len()]),
+            reaches_dynamic: reaching(&predecessors, dynamic),
+        }
+    }
+
+    /// Whether running from this line reaches the end of the program
+    pub fn terminates_from(&self, line: usize) -> bool {
+        self.terminates.get(line).copied().unwrap_or(false)
+    }
+
+    /// Whether running from this line reaches code that can only be followed by running it
+    pub fn reaches_dynamic_from(&self, line: usize) -> bool {
+        self.reaches_dynamic.get(line).copied().unwrap_or(false)
+    }
+}
+
+/// A single changed instruction that makes the program terminate
+#[derive(Debug, Eq, PartialEq)]
+pub struct Fix {
+    pub line: usize,
+    pub instruction: Instruction,
+    /// The accumulator when the fixed program ends
+    pub accumulator: isize,
+}
+
+/// Find every single jmp/nop flip that makes a looping program terminate.
+/// Only lines the original run reaches can matter, and flipping one of them works exactly when
+/// its new successor leads to the end, since the rest of the path can't loop back through it
+/// without the original program terminating too. A flip that leads into a conditional jump is
+/// run to see where it goes, and flips whose run still fails are left out. Conditional jumps
+/// are only an error if the original run reaches one.
+pub fn find_fixes(program: &Program, max_steps: usize) -> Result<Vec<Fix>, RepairError> {
+    let flow = ControlFlow::new(program);
+    if flow.terminates_from(0) {
+        return Ok(Vec::new());
+    }
+
+    let mut fixes: Vec<Fix> = Vec::new();
+    let mut lines_visited: HashSet<usize> = HashSet::new();
+    let mut line = 0;
+
+    while line < program.len() && lines_visited.insert(line) {
+        let instruction = program[line];
+        if let Some(opcode) = flipped(instruction.opcode) {
+            let fixed = Instruction { opcode, ..instruction };
+            let next = successor(program, line, &fixed)?;
+            let might_end = |next| flow.terminates_from(next) || flow.reaches_dynamic_from(next);
+            if next.is_some_and(might_end) {
+                let mut program_fix = program.clone();
+                program_fix[line] = fixed;
+                // Reaching the end can still overflow the accumulator on the way, and conditional
+                // jumps can still loop
+                if let Ok(accumulator) = run_program_with_limit(&program_fix, max_steps) {
+                    fixes.push(Fix { line, instruction: fixed, accumulator });
+                }
+            }
+        }
+
+        line = match successor(program, line, &instruction)? {
+            Some(next) => next,
+            None => break,
+        };
+    }
+
+    Ok(fixes)
+}
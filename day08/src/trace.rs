@@ -0,0 +1,183 @@
+use crate::instruction::{parse_instruction, Instruction, Program};
+use crate::{ProgramError, ProgramState};
+use std::collections::HashSet;
+use std::fmt::Display;
+
+/// One executed instruction
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TraceStep {
+    pub line: usize,
+    pub instruction: Instruction,
+    pub acc_before: isize,
+    pub acc_after: isize,
+}
+
+impl Display for TraceStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {} {}", self.line, self.instruction, self.acc_before, self.acc_after)
+    }
+}
+
+/// How a traced run stopped
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TraceEnd {
+    Finished(isize),
+    /// The line that would have run a second time
+    Loop(usize),
+    Error(ProgramError),
+}
+
+impl Display for TraceEnd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TraceEnd::Finished(acc) => write!(f, "end finished {}", acc),
+            TraceEnd::Loop(line) => write!(f, "end loop {}", line),
+            TraceEnd::Error(ProgramError::InfiniteLoop(acc)) => write!(f, "end error loop {}", acc),
+            TraceEnd::Error(ProgramError::JumpOutOfBounds(line, target)) =>
+                write!(f, "end error jump {} {}", line, target),
+            TraceEnd::Error(ProgramError::AccumulatorOverflow(line)) =>
+                write!(f, "end error overflow {}", line),
+            TraceEnd::Error(ProgramError::StepLimitExceeded(limit)) =>
+                write!(f, "end error limit {}", limit),
+        }
+    }
+}
+
+/// Every instruction a run executed, in order, and how it stopped
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Trace {
+    pub steps: Vec<TraceStep>,
+    pub end: TraceEnd,
+}
+
+/// A trace file line that couldn't be read, with its line number
+#[derive(Debug, Eq, PartialEq)]
+pub struct TraceParseError(pub usize, pub String);
+
+impl Display for TraceParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "trace line {}: could not read \"{}\"", self.0, self.1)
+    }
+}
+
+impl Trace {
+    /// Run a program until it ends, loops or runs max_steps instructions, recording every step
+    pub fn record(program: &Program, max_steps: usize) -> Self {
+        let mut state = ProgramState::with_step_limit(program, max_steps);
+        let mut states_visited: HashSet<(usize, Option<isize>)> = HashSet::new();
+        let mut steps: Vec<TraceStep> = Vec::new();
+
+        let end = loop {
+            let line = state.next_line;
+            if !states_visited.insert(state.loop_state()) {
+                break TraceEnd::Loop(line);
+            }
+            if line == program.len() {
+                break TraceEnd::Finished(state.accumulator);
+            }
+
+            let acc_before = state.accumulator;
+            if let Err(e) = state.step() {
+                break TraceEnd::Error(e);
+            }
+            steps.push(TraceStep {
+                line,
+                instruction: program[line],
+                acc_before,
+                acc_after: state.accumulator,
+            });
+        };
+
+        Self { steps, end }
+    }
+
+    /// Read a trace written by to_file_string
+    pub fn from_file_string(contents: &str) -> Result<Self, TraceParseError> {
+        let mut steps: Vec<TraceStep> = Vec::new();
+
+        for (index, text) in contents.lines().enumerate() {
+            let error = || TraceParseError(index, text.to_string());
+            let words: Vec<&str> = text.split_whitespace().collect();
+
+            if words.first() == Some(&"end") {
+                // Lines and limits can't be negative, so "end loop -1" doesn't wrap around
+                let count = |word: &str| word.parse::<usize>().map_err(|_| error());
+                let value = |word: &str| word.parse::<isize>().map_err(|_| error());
+                let end = match words[1..] {
+                    ["finished", acc] => TraceEnd::Finished(value(acc)?),
+                    ["loop", line] => TraceEnd::Loop(count(line)?),
+                    ["error", "loop", acc] =>
+                        TraceEnd::Error(ProgramError::InfiniteLoop(value(acc)?)),
+                    ["error", "jump", line, target] => TraceEnd::Error(
+                        ProgramError::JumpOutOfBounds(count(line)?, value(target)?)
+                    ),
+                    ["error", "overflow", line] =>
+                        TraceEnd::Error(ProgramError::AccumulatorOverflow(count(line)?)),
+                    ["error", "limit", limit] =>
+                        TraceEnd::Error(ProgramError::StepLimitExceeded(count(limit)?)),
+                    _ => return Err(error()),
+                };
+                return Ok(Self { steps, end });
+            }
+
+            match words[..] {
+                [line, opcode, argument, before, after] => {
+                    let line: usize = line.parse().map_err(|_| error())?;
+                    steps.push(TraceStep {
+                        line,
+                        instruction: parse_instruction(line, &format!("{} {}", opcode, argument))
+                            .map_err(|_| error())?,
+                        acc_before: before.parse().map_err(|_| error())?,
+                        acc_after: after.parse().map_err(|_| error())?,
+                    });
+                }
+                _ => return Err(error()),
+            }
+        }
+
+        Err(TraceParseError(contents.lines().count(), "missing end line".to_string()))
+    }
+
+    /// Write the trace one step per line, with a final line for how the run ended
+    pub fn to_file_string(&self) -> String {
+        let mut contents: String = self.steps
+            .iter()
+            .map(|step| format!("{}\n", step))
+            .collect();
+        contents.push_str(&self.end.to_string());
+        contents.push('\n');
+        contents
+    }
+}
+
+/// Where two traces first disagree
+#[derive(Debug, Eq, PartialEq)]
+pub struct Divergence {
+    /// Index of the first differing step
+    pub step: usize,
+    /// The steps at that index, None if that trace had already ended
+    pub left: Option<TraceStep>,
+    pub right: Option<TraceStep>,
+}
+
+impl Display for Divergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let show = |step: &Option<TraceStep>| step.map_or("<ended>".to_string(), |s| s.to_string());
+        write!(f, "step {}:\n< {}\n> {}", self.step, show(&self.left), show(&self.right))
+    }
+}
+
+/// Find the first step where two runs differ, or None if they're identical.
+/// Runs that execute the same steps but end differently diverge just past the last step.
+pub fn first_divergence(left: &Trace, right: &Trace) -> Option<Divergence> {
+    let longest = left.steps.len().max(right.steps.len());
+
+    (0..longest)
+        .find(|&step| left.steps.get(step) != right.steps.get(step))
+        .or(if left.end != right.end { Some(longest) } else { None })
+        .map(|step| Divergence {
+            step,
+            left: left.steps.get(step).copied(),
+            right: right.steps.get(step).copied(),
+        })
+}
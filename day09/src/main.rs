@@ -1,130 +1,61 @@
-// Part 1 of this is a bit too clever for its own good, but it was a fun exercise
-// in trying out and practicing smart pointers.
+mod xmas;
+
 use std::{
-    collections::{HashSet, HashMap},
     env,
-    fs,
-    ops::Deref,
-    rc::{Weak, Rc},
+    fs::File,
+    io::BufReader,
 };
-use weak_table::WeakHashSet;
+use crate::xmas::{find_ranges, invalid_numbers, read_numbers, WeaknessError};
 
-// Adapted from the Symbol example for WeakHashSet
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
-struct ValidSum(Rc<usize>);
+const DEFAULT_WINDOW: usize = 25;
 
-impl Deref for ValidSum {
-    type Target = usize;
-    fn deref(&self) -> &usize {
-        self.0.deref()
-    }
+/// The first number that isn't the sum of two of the window numbers before it
+fn part1(numbers: &[usize], window: usize) -> Option<usize> {
+    invalid_numbers(numbers.iter().copied(), window)
+        .next()
+        .map(|(_, num)| num)
 }
 
-/// A set of in-use interned integers, to track pair sums in the current window.
-#[derive(Debug, Default)]
-struct ValidSumTable(WeakHashSet<Weak<usize>>);
-
-/// A map of numbers to sets of sums for which the number is the first number
-/// contributing to the sum
-type SumOriginMap = HashMap<usize, HashSet<ValidSum>>;
-
-impl ValidSumTable {
-    fn new() -> Self {
-        Self::default()
-    }
-
-    /// Ensure we're counting references correctly by confining each number to a single reference
-    fn intern(&mut self, sum: usize) -> ValidSum {
-        if let Some(rc) = self.0.get(&sum) {
-            ValidSum(rc)
-        } else {
-            let rc = Rc::from(sum);
-            self.0.insert(Rc::clone(&rc));
-            ValidSum(rc)
-        }
-    }
+/// The sum of the smallest and largest numbers in the first run that adds up to target
+fn part2(numbers: &[usize], target: usize) -> Result<usize, WeaknessError> {
+    find_ranges(numbers, target).map(|ranges| ranges[0].weakness())
 }
 
-/// Interns a number and adds it to the map and interning table, updating the sum sets
-/// of previous numbers.
-fn add_to_window(sum_map: &mut SumOriginMap, sum_table: &mut ValidSumTable, num: usize) {
-    for (add, set) in sum_map.iter_mut() {
-        set.insert(sum_table.intern(add + num));
-    }
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let filename = &args[1];
 
-    sum_map.insert(num, HashSet::new());
-}
+    // Pass a window size after the filename to use something other than 25
+    let window = args
+        .get(2)
+        .map_or(DEFAULT_WINDOW, |w| w.parse().expect("Window size must be a number"));
 
-fn part1(numbers: &Vec<usize>, window: usize) -> usize {
-    let mut sum_map: SumOriginMap = HashMap::new();
-    let mut sum_table = ValidSumTable::new();
+    let file = File::open(filename).expect("Error opening file");
+    let numbers: Vec<usize> = read_numbers(BufReader::new(file))
+        .collect::<Result<_, _>>()
+        .unwrap_or_else(|e| panic!("Invalid input: {}", e));
 
-    // Get initial sums
-    let preamble = &numbers[0..window];
-    for num in preamble.iter() {
-        add_to_window(&mut sum_map, &mut sum_table, *num);
+    let invalid: Vec<(usize, usize)> = invalid_numbers(numbers.iter().copied(), window).collect();
+    for (index, num) in invalid.iter() {
+        println!("Invalid number {} at index {}", num, index);
     }
 
-    numbers
-        .clone()
-        .iter()
-        .zip(numbers.iter().skip(window)) // Pair numbers leaving and entering window as it slides
-        .find_map(|(exiting, entering)| {
-            if !sum_table.0.contains(entering) {
-                Some(entering)
-            } else {
-                // Time for side effects!
-                sum_map.remove(exiting);
-                add_to_window(&mut sum_map, &mut sum_table, *entering);
-                None
+    let answer1 = part1(&numbers, window).expect("Every number is valid");
+    println!("Part 1: {}", answer1);
+    match find_ranges(&numbers, answer1) {
+        Ok(ranges) => {
+            for range in ranges.iter().skip(1) {
+                println!(
+                    "Numbers {} to {} also sum to {} (weakness {})",
+                    range.start, range.end, answer1, range.weakness()
+                );
             }
-        })
-        .copied()
-        .unwrap()
-}
-
-fn part2(numbers: &Vec<usize>, target: usize) -> usize {
-    let mut index = 0usize;
-
-    loop {
-        if index >= numbers.len() {
-            panic!("Failed to find encryption weakness");
-        }
-        let mut acc = 0usize;
-        let candidates: Vec<usize> = numbers
-            .iter()
-            .skip(index)
-            .take_while(|num| {
-                // More side effects!!
-                if acc >= target {
-                    false
-                } else {
-                    acc += *num;
-                    true
-                }
-            })
-            .copied()
-            .collect();
-
-        if acc == target {
-            break candidates.iter().min().unwrap() + candidates.iter().max().unwrap();
+            println!("Part 2: {}", part2(&numbers, answer1).unwrap());
         }
-
-        index += 1;
+        Err(e) => println!("Part 2: {}", e),
     }
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    let filename = &args[1];
-
-    let contents = fs::read_to_string(filename).expect("Error opening file");
-    let numbers: Vec<usize> = contents.lines().map(|l| l.parse().unwrap()).collect();
-    let answer1 = part1(&numbers, 25);
-    println!("Part 1: {}", answer1);
-    println!("Part 2: {}", part2(&numbers, answer1));
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -136,7 +67,20 @@ mod tests {
             117, 150, 182, 127, 219, 299, 277, 309, 576,
         ];
 
-        assert_eq!(part1(&sample, 5), 127);
+        assert_eq!(part1(&sample, 5), Some(127));
+    }
+
+    #[test]
+    fn every_invalid_number() {
+        let numbers = "1\n2\n3\n4\n20\n7\n6\n14\n13\n".as_bytes();
+        let numbers: Vec<usize> = read_numbers(numbers).map(Result::unwrap).collect();
+
+        // 14 can't use 7 twice
+        assert_eq!(
+            invalid_numbers(numbers, 3).collect::<Vec<_>>(),
+            vec![(4, 20), (6, 6), (7, 14)]
+        );
+        assert!(read_numbers("1\nx\n".as_bytes()).nth(1).unwrap().is_err());
     }
 
     #[test]
@@ -146,6 +90,25 @@ mod tests {
             117, 150, 182, 127, 219, 299, 277, 309, 576,
         ];
 
-        assert_eq!(part2(&sample, 127), 62);
+        assert_eq!(part2(&sample, 127), Ok(62));
+    }
+
+    #[test]
+    fn every_range() {
+        use crate::xmas::SumRange;
+
+        // Zeros let several runs share the same sum
+        let numbers = [5, 0, 3, 2, 0, 8];
+        let ranges = find_ranges(&numbers, 5).unwrap();
+        let bounds: Vec<(usize, usize)> = ranges.iter().map(|r| (r.start, r.end)).collect();
+        assert_eq!(bounds, vec![(0, 1), (1, 3), (1, 4), (2, 3), (2, 4)]);
+        assert_eq!(ranges[3], SumRange { start: 2, end: 3, min: 2, max: 3 });
+
+        // The 8 on its own isn't a run
+        assert_eq!(find_ranges(&numbers, 8), Ok(vec![
+            SumRange { start: 0, end: 2, min: 0, max: 5 },
+            SumRange { start: 4, end: 5, min: 0, max: 8 },
+        ]));
+        assert_eq!(find_ranges(&numbers, 100), Err(WeaknessError::NotFound(100)));
     }
 }
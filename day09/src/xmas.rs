@@ -0,0 +1,165 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Display,
+    io::{self, BufRead},
+};
+
+/// Checks each number against the sums of pairs in the window of numbers before it
+#[derive(Debug)]
+pub struct XmasValidator {
+    size: usize,
+    window: VecDeque<usize>,
+    /// How many times each number appears in the window
+    counts: HashMap<usize, usize>,
+}
+
+impl XmasValidator {
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            window: VecDeque::with_capacity(size + 1),
+            counts: HashMap::new(),
+        }
+    }
+
+    /// Whether two different numbers in the window sum to num
+    fn is_pair_sum(&self, num: usize) -> bool {
+        self.window
+            .iter()
+            .any(|&first| first < num && first * 2 != num && self.counts.contains_key(&(num - first)))
+    }
+
+    /// Check a number and slide it into the window. Numbers in the preamble are always valid.
+    pub fn push(&mut self, num: usize) -> bool {
+        let valid = self.window.len() < self.size || self.is_pair_sum(num);
+
+        self.window.push_back(num);
+        self.counts.entry(num).and_modify(|c| *c += 1).or_insert(1);
+        if self.window.len() > self.size {
+            let exiting = self.window.pop_front().unwrap();
+            if self.counts[&exiting] == 1 {
+                self.counts.remove(&exiting);
+            } else {
+                self.counts.entry(exiting).and_modify(|c| *c -= 1);
+            }
+        }
+
+        valid
+    }
+}
+
+/// Iterator over the invalid numbers in a stream, with their indices
+pub struct InvalidNumbers<I> {
+    numbers: std::iter::Enumerate<I>,
+    validator: XmasValidator,
+}
+
+impl<I: Iterator<Item = usize>> Iterator for InvalidNumbers<I> {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let validator = &mut self.validator;
+        self.numbers.find(|&(_, num)| !validator.push(num))
+    }
+}
+
+/// Every number that isn't the sum of two different numbers in the window before it,
+/// as (index, number) pairs
+pub fn invalid_numbers<I>(numbers: I, window: usize) -> InvalidNumbers<I::IntoIter>
+where
+    I: IntoIterator<Item = usize>,
+{
+    InvalidNumbers {
+        numbers: numbers.into_iter().enumerate(),
+        validator: XmasValidator::new(window),
+    }
+}
+
+#[derive(Debug)]
+pub enum ReadError {
+    Io(io::Error),
+    /// Line number and the text that isn't a number
+    InvalidNumber(usize, String),
+}
+
+impl Display for ReadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReadError::Io(e) => write!(f, "{}", e),
+            ReadError::InvalidNumber(line, text) =>
+                write!(f, "line {}: \"{}\" is not a number", line, text),
+        }
+    }
+}
+
+/// Read one number per line, without loading the whole input
+pub fn read_numbers(reader: impl BufRead) -> impl Iterator<Item = Result<usize, ReadError>> {
+    reader.lines().enumerate().map(|(line, text)| {
+        let text = text.map_err(ReadError::Io)?;
+        text.trim()
+            .parse()
+            .map_err(|_| ReadError::InvalidNumber(line, text.clone()))
+    })
+}
+
+/// A run of at least two consecutive numbers, by index, and its smallest and largest numbers
+#[derive(Debug, Eq, PartialEq)]
+pub struct SumRange {
+    pub start: usize,
+    pub end: usize,
+    pub min: usize,
+    pub max: usize,
+}
+
+impl SumRange {
+    pub fn weakness(&self) -> usize {
+        self.min + self.max
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum WeaknessError {
+    /// No run of two or more numbers sums to the target
+    NotFound(usize),
+}
+
+impl Display for WeaknessError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WeaknessError::NotFound(target) =>
+                write!(f, "no run of two or more numbers sums to {}", target),
+        }
+    }
+}
+
+/// Every run of at least two consecutive numbers that sums to target, ordered by start then end.
+/// Uses prefix sums, so finding the runs is linear even when the input has zeros; each run found
+/// is then scanned once for its bounds.
+pub fn find_ranges(numbers: &[usize], target: usize) -> Result<Vec<SumRange>, WeaknessError> {
+    // Where each prefix sum occurs, as the index just past the numbers it covers
+    let mut prefix_starts: HashMap<usize, Vec<usize>> = HashMap::new();
+    let mut prefix = 0usize;
+    let mut ranges: Vec<SumRange> = Vec::new();
+
+    for (end, &num) in numbers.iter().enumerate() {
+        prefix_starts.entry(prefix).or_default().push(end);
+        prefix += num;
+
+        let starts = prefix.checked_sub(target).and_then(|p| prefix_starts.get(&p));
+        for &start in starts.into_iter().flatten().filter(|&&start| start < end) {
+            let run = &numbers[start..=end];
+            ranges.push(SumRange {
+                start,
+                end,
+                min: *run.iter().min().unwrap(),
+                max: *run.iter().max().unwrap(),
+            });
+        }
+    }
+
+    if ranges.is_empty() {
+        return Err(WeaknessError::NotFound(target));
+    }
+    ranges.sort_by_key(|range| (range.start, range.end));
+    Ok(ranges)
+}
@@ -0,0 +1,182 @@
+use crate::{adapter_possibilities, joltage_chain, Tolerance};
+use num::{BigUint, Zero};
+
+/// Every valid way to chain a bag of adapters from the outlet to the device
+pub struct AdapterChains<'a> {
+    tolerance: &'a Tolerance,
+    /// Outlet, adapters and device, in joltage order
+    chain: Vec<usize>,
+    /// How many ways there are to get from each position to the device
+    to_end: Vec<BigUint>,
+}
+
+impl<'a> AdapterChains<'a> {
+    pub fn new(adapters: &[usize], tolerance: &'a Tolerance) -> Self {
+        let chain = joltage_chain(adapters, tolerance);
+        let to_end = adapter_possibilities(&chain, tolerance);
+        Self { tolerance, chain, to_end }
+    }
+
+    pub fn count(&self) -> &BigUint {
+        &self.to_end[0]
+    }
+
+    fn end(&self) -> usize {
+        self.chain.len() - 1
+    }
+
+    /// Positions one step on from position that still lead to the device, in joltage order
+    fn successors(&self, position: usize) -> impl Iterator<Item = usize> + '_ {
+        let joltage = self.chain[position];
+        (position + 1..self.chain.len())
+            .take_while(move |&next| self.chain[next] - joltage <= self.tolerance.largest())
+            .filter(move |&next| {
+                self.tolerance.allows(self.chain[next] - joltage) && !self.to_end[next].is_zero()
+            })
+    }
+
+    fn joltages(&self, positions: &[usize]) -> Vec<usize> {
+        positions.iter().map(|&position| self.chain[position]).collect()
+    }
+
+    /// Every chain, lazily, ordered by the joltages they use
+    pub fn iter(&self) -> Chains<'_, 'a> {
+        Chains { chains: self, path: Vec::new(), started: false }
+    }
+
+    /// The chain iter would yield after skipping rank chains, found without visiting the others
+    pub fn chain_at(&self, rank: &BigUint) -> Option<Vec<usize>> {
+        if rank >= self.count() {
+            return None;
+        }
+
+        let mut rank = rank.clone();
+        let mut path = vec![0];
+        while path[path.len() - 1] != self.end() {
+            let position = path[path.len() - 1];
+            for next in self.successors(position) {
+                if rank < self.to_end[next] {
+                    path.push(next);
+                    break;
+                }
+                rank -= &self.to_end[next];
+            }
+        }
+        Some(self.joltages(&path))
+    }
+
+    /// Up to count chains spread evenly through the order iter uses
+    pub fn sample(&self, count: usize) -> impl Iterator<Item = Vec<usize>> + '_ {
+        let count = BigUint::from(count).min(self.count().clone());
+        let step_count = count.clone();
+        num::range(BigUint::zero(), count)
+            .map(move |i| self.chain_at(&(i * self.count() / &step_count)).unwrap())
+    }
+
+    /// The chain with the fewest or most adapters, breaking ties towards earlier chains
+    fn extreme(&self, better: impl Fn(usize, usize) -> bool) -> Option<Vec<usize>> {
+        if self.count().is_zero() {
+            return None;
+        }
+
+        // Best number of steps to the device from each position, and the step to take
+        let mut steps = vec![0; self.chain.len()];
+        let mut best_next = vec![self.end(); self.chain.len()];
+        for position in (0..self.end()).rev().filter(|&p| !self.to_end[p].is_zero()) {
+            let best = self.successors(position)
+                .map(|next| (steps[next] + 1, next))
+                .fold(None, |best, candidate| match best {
+                    Some((best_steps, _)) if !better(candidate.0, best_steps) => best,
+                    _ => Some(candidate),
+                });
+            if let Some((best_steps, next)) = best {
+                steps[position] = best_steps;
+                best_next[position] = next;
+            }
+        }
+
+        let mut path = vec![0];
+        while path[path.len() - 1] != self.end() {
+            path.push(best_next[path[path.len() - 1]]);
+        }
+        Some(self.joltages(&path))
+    }
+
+    pub fn shortest(&self) -> Option<Vec<usize>> {
+        self.extreme(|steps, best| steps < best)
+    }
+
+    pub fn longest(&self) -> Option<Vec<usize>> {
+        self.extreme(|steps, best| steps > best)
+    }
+
+    /// Adapters that every chain uses. A position is on every chain exactly when the ways to
+    /// reach it times the ways to carry on from it accounts for every chain.
+    pub fn mandatory(&self) -> Vec<usize> {
+        if self.count().is_zero() {
+            return Vec::new();
+        }
+
+        let mut from_start: Vec<BigUint> = vec![BigUint::zero(); self.chain.len()];
+        from_start[0] = BigUint::from(1u32);
+        for position in 0..self.end() {
+            if from_start[position].is_zero() {
+                continue;
+            }
+            let ways = from_start[position].clone();
+            for next in self.successors(position) {
+                from_start[next] += &ways;
+            }
+        }
+
+        (1..self.end())
+            .filter(|&position| &from_start[position] * &self.to_end[position] == *self.count())
+            .map(|position| self.chain[position])
+            .collect()
+    }
+}
+
+/// Depth-first walk over the chains, skipping positions that can't reach the device
+pub struct Chains<'c, 'a> {
+    chains: &'c AdapterChains<'a>,
+    /// Positions in the chain last yielded
+    path: Vec<usize>,
+    started: bool,
+}
+
+impl Chains<'_, '_> {
+    /// Take the first step each time until reaching the device
+    fn extend(&mut self) {
+        while let Some(&position) = self.path.last().filter(|&&p| p != self.chains.end()) {
+            let next = self.chains.successors(position).next().expect("Position leads to the device");
+            self.path.push(next);
+        }
+    }
+}
+
+impl Iterator for Chains<'_, '_> {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.started {
+            self.started = true;
+            if self.chains.count().is_zero() {
+                return None;
+            }
+            self.path.push(0);
+        } else {
+            // Back up to the latest position with another way forward
+            loop {
+                let last = self.path.pop()?;
+                let &position = self.path.last()?;
+                if let Some(next) = self.chains.successors(position).find(|&next| next > last) {
+                    self.path.push(next);
+                    break;
+                }
+            }
+        }
+
+        self.extend();
+        Some(self.chains.joltages(&self.path))
+    }
+}
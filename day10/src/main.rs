@@ -1,13 +1,82 @@
-use std::{env, fs, collections::HashMap};
+mod chains;
 
-fn part1(adapters: &Vec<usize>) -> usize {
-    let mut all_adapters = adapters.clone();
-    all_adapters.push(0); // Account for charging outlet
-    all_adapters.sort_unstable();
+use std::{env, fs, collections::{BTreeSet, HashMap}, fmt::Display};
+use num::{BigUint, One, Zero};
+use crate::chains::AdapterChains;
 
-    let diff_iter = all_adapters
+/// The joltage differences an adapter can take from the one before it. The device always takes
+/// the largest difference from the highest adapter.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Tolerance(BTreeSet<usize>);
+
+#[derive(Debug, Eq, PartialEq)]
+enum ToleranceError {
+    Empty,
+    /// Adapters can't take their own joltage
+    ZeroDifference,
+    InvalidDifference(String),
+}
+
+impl Display for ToleranceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ToleranceError::Empty => write!(f, "no differences given"),
+            ToleranceError::ZeroDifference => write!(f, "differences must be at least 1"),
+            ToleranceError::InvalidDifference(text) => write!(f, "\"{}\" is not a difference", text),
+        }
+    }
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Self([1, 2, 3].iter().copied().collect())
+    }
+}
+
+impl Tolerance {
+    fn new(differences: impl IntoIterator<Item = usize>) -> Result<Self, ToleranceError> {
+        let differences: BTreeSet<usize> = differences.into_iter().collect();
+        if differences.is_empty() {
+            Err(ToleranceError::Empty)
+        } else if differences.contains(&0) {
+            Err(ToleranceError::ZeroDifference)
+        } else {
+            Ok(Self(differences))
+        }
+    }
+
+    /// Parse a comma-separated list of differences, like "1,3,5"
+    fn from_list(list: &str) -> Result<Self, ToleranceError> {
+        let differences = list
+            .split(',')
+            .map(|d| d.trim().parse().map_err(|_| ToleranceError::InvalidDifference(d.to_string())))
+            .collect::<Result<Vec<usize>, _>>()?;
+        Self::new(differences)
+    }
+
+    fn allows(&self, difference: usize) -> bool {
+        self.0.contains(&difference)
+    }
+
+    fn largest(&self) -> usize {
+        *self.0.iter().next_back().unwrap()
+    }
+}
+
+/// The charging outlet, every adapter in joltage order, then the device
+fn joltage_chain(adapters: &[usize], tolerance: &Tolerance) -> Vec<usize> {
+    let mut chain = adapters.to_vec();
+    chain.push(0); // Account for charging outlet
+    chain.sort_unstable();
+    chain.push(chain.last().unwrap() + tolerance.largest());
+    chain
+}
+
+fn part1(adapters: &[usize], tolerance: &Tolerance) -> usize {
+    let chain = joltage_chain(adapters, tolerance);
+    let diff_iter = chain
         .windows(2)
-        .map(|window| window.get(1).unwrap() - window.get(0).unwrap());
+        .map(|window| window[1] - window[0]);
 
     let mut frequencies: HashMap<usize, usize> = HashMap::new();
     for diff in diff_iter {
@@ -17,61 +86,70 @@ fn part1(adapters: &Vec<usize>) -> usize {
             .or_insert(1);
     }
 
-    frequencies
-        .get(&1)
-        .copied()
-        .unwrap_or(0) *
-        (frequencies
-            .get(&3)
-            .copied()
-            .unwrap_or(0)
-            + 1) // Include the device
+    frequencies.get(&1).copied().unwrap_or(0) * frequencies.get(&3).copied().unwrap_or(0)
 }
 
-/// Checks number of valid chains for a given list of adapters, storing results against the
-/// value of the first adapter in memos to reduce iteration
-fn adapter_possibilities(adapters: &[usize], memos: &mut HashMap<usize, usize>) -> usize {
-    match adapters.split_first() {
-        Some((&first, rest)) => {
-            if let Some(&memo) = memos.get(&first) {
-                memo
-            } else if rest.len() == 0 {
-                memos.insert(first, 1);
-                1
-            } else {
-                let mut acc = 0;
-                let mut rest_candidate = rest.clone();
-                loop {
-                    match rest_candidate.first() {
-                        Some(next) if next - first <= 3 =>
-                            acc += adapter_possibilities(rest_candidate, memos),
-                        _ => break,
-                    }
-                    rest_candidate = &rest_candidate[1..];
-                }
-                memos.insert(first, acc);
-                acc
-            }
-        }
-        None => panic!("Base case should occur before we're out of adapters"),
+/// Counts the ways to get from each position in a joltage chain to its end, working back from the
+/// end so each position only needs the counts of the few positions just after it
+fn adapter_possibilities(chain: &[usize], tolerance: &Tolerance) -> Vec<BigUint> {
+    let mut possibilities: Vec<BigUint> = vec![BigUint::zero(); chain.len()];
+    if let Some(last) = possibilities.last_mut() {
+        *last = BigUint::one();
     }
+
+    for (index, &joltage) in chain.iter().enumerate().rev().skip(1) {
+        possibilities[index] = (index + 1..chain.len())
+            .take_while(|&next| chain[next] - joltage <= tolerance.largest())
+            .filter(|&next| tolerance.allows(chain[next] - joltage))
+            .map(|next| &possibilities[next])
+            .sum();
+    }
+
+    possibilities
 }
 
-fn part2(adapters: &Vec<usize>) -> usize {
-    let mut all_adapters = adapters.clone();
-    all_adapters.push(0);
-    all_adapters.sort_unstable();
-    adapter_possibilities(&all_adapters[..], &mut HashMap::new())
+fn part2(adapters: &[usize], tolerance: &Tolerance) -> BigUint {
+    adapter_possibilities(&joltage_chain(adapters, tolerance), tolerance).swap_remove(0)
 }
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     let filename = &args[1];
 
+    // Pass a comma-separated list of allowed differences after the filename, like "1,3,5", then
+    // "chains [N]" to see the shortest, longest and N sample chains, and the mandatory adapters
+    let mut options = args[2..].iter().map(String::as_str).peekable();
+    let tolerance = options
+        .next_if(|&option| option != "chains")
+        .map_or_else(|| Ok(Tolerance::default()), Tolerance::from_list)
+        .unwrap_or_else(|e| panic!("Invalid differences: {}", e));
+    let show_chains = options.next() == Some("chains");
+    let sample_count: usize = options
+        .next()
+        .map_or(10, |count| count.parse().expect("Sample count must be a number"));
+
     let contents = fs::read_to_string(filename).expect("Error opening file");
     let numbers: Vec<usize> = contents.lines().map(|l| l.parse().unwrap()).collect();
-    println!("Part 1: {}", part1(&numbers));
-    println!("Part 2: {}", part2(&numbers));
+    println!("Part 1: {}", part1(&numbers, &tolerance));
+    println!("Part 2: {}", part2(&numbers, &tolerance));
+
+    if show_chains {
+        let chains = AdapterChains::new(&numbers, &tolerance);
+        let show = |chain: Option<Vec<usize>>| chain.map_or("none".to_string(), |c| format!("{:?}", c));
+        println!("Shortest chain: {}", show(chains.shortest()));
+        println!("Longest chain: {}", show(chains.longest()));
+        println!("Mandatory adapters: {:?}", chains.mandatory());
+        let listed: Box<dyn Iterator<Item = Vec<usize>>> =
+            if *chains.count() <= BigUint::from(sample_count) {
+                Box::new(chains.iter())
+            } else {
+                println!("Sample of {} chains:", sample_count);
+                Box::new(chains.sample(sample_count))
+            };
+        for chain in listed {
+            println!("{:?}", chain);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -85,8 +163,8 @@ mod tests {
                            45, 19, 38, 39, 11, 1, 32, 25, 35, 8, 17, 7, 9, 4,
                            2, 34, 10, 3];
 
-        assert_eq!(part1(&sample1), 35);
-        assert_eq!(part1(&sample2), 220);
+        assert_eq!(part1(&sample1, &Tolerance::default()), 35);
+        assert_eq!(part1(&sample2, &Tolerance::default()), 220);
     }
 
     #[test]
@@ -96,7 +174,57 @@ mod tests {
                            45, 19, 38, 39, 11, 1, 32, 25, 35, 8, 17, 7, 9, 4,
                            2, 34, 10, 3];
 
-        assert_eq!(part2(&sample1), 8);
-        assert_eq!(part2(&sample2), 19208);
+        assert_eq!(part2(&sample1, &Tolerance::default()), BigUint::from(8u32));
+        assert_eq!(part2(&sample2, &Tolerance::default()), BigUint::from(19208u32));
+    }
+
+    #[test]
+    fn other_tolerances() {
+        // 0, 1, 3, 4, 5, 6, 8 then the device at 13
+        let adapters = vec![1, 3, 4, 5, 6, 8];
+        let odd = Tolerance::from_list("1,3,5").unwrap();
+        assert_eq!(joltage_chain(&adapters, &odd).last(), Some(&13));
+        // 6 is a dead end, leaving 0-1-4-5-8, 0-3-4-5-8, 0-3-8 and 0-5-8
+        assert_eq!(part2(&adapters, &odd), BigUint::from(4u32));
+
+        assert_eq!(Tolerance::from_list(""), Err(ToleranceError::InvalidDifference("".to_string())));
+        assert_eq!(Tolerance::from_list("0,1"), Err(ToleranceError::ZeroDifference));
+        assert_eq!(Tolerance::new(vec![]), Err(ToleranceError::Empty));
+    }
+
+    #[test]
+    fn chain_structure() {
+        let sample1 = vec![16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4];
+        let tolerance = Tolerance::default();
+        let chains = AdapterChains::new(&sample1, &tolerance);
+
+        let all: Vec<Vec<usize>> = chains.iter().collect();
+        assert_eq!(all.len(), 8);
+        assert_eq!(all[0], vec![0, 1, 4, 5, 6, 7, 10, 11, 12, 15, 16, 19, 22]);
+        assert_eq!(all[7], vec![0, 1, 4, 7, 10, 12, 15, 16, 19, 22]);
+        for (rank, chain) in all.iter().enumerate() {
+            assert_eq!(chains.chain_at(&BigUint::from(rank)).as_ref(), Some(chain));
+        }
+        assert_eq!(chains.chain_at(&BigUint::from(8u32)), None);
+        assert_eq!(chains.sample(3).collect::<Vec<_>>(), vec![all[0].clone(), all[2].clone(), all[5].clone()]);
+        assert_eq!(chains.sample(20).count(), 8);
+
+        assert_eq!(chains.shortest(), Some(all[7].clone()));
+        assert_eq!(chains.longest(), Some(all[0].clone()));
+        assert_eq!(chains.mandatory(), vec![1, 4, 7, 10, 12, 15, 16, 19]);
+
+        // A gap too wide to cross
+        let stuck = AdapterChains::new(&[1, 5], &tolerance);
+        assert_eq!(stuck.iter().next(), None);
+        assert_eq!(stuck.shortest(), None);
+        assert_eq!(stuck.mandatory(), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn counts_past_usize() {
+        // Every subset of the middle adapters works, so this is 2^100 arrangements
+        let adapters: Vec<usize> = (1..=101).collect();
+        let tolerance = Tolerance::new(1..=101).unwrap();
+        assert_eq!(part2(&adapters, &tolerance), BigUint::one() << 100);
     }
 }
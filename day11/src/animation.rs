@@ -0,0 +1,34 @@
+use crate::{Ferry, Seat};
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, Write},
+    thread,
+    time::Duration,
+};
+
+/// Render each generation as a text frame with a heading
+pub fn render_frames(ferry: &Ferry, generations: &[HashSet<Seat>]) -> Vec<String> {
+    generations
+        .iter()
+        .enumerate()
+        .map(|(generation, occupied)| format!("Generation {}\n{}", generation, ferry.render(occupied)))
+        .collect()
+}
+
+/// Show the frames one after another in the terminal, redrawing in place
+pub fn play(frames: &[String], delay: Duration) {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    for frame in frames {
+        // Clear the screen and move the cursor back to the top left
+        writeln!(out, "\x1b[2J\x1b[H{}", frame).expect("Error writing to stdout");
+        out.flush().expect("Error writing to stdout");
+        thread::sleep(delay);
+    }
+}
+
+/// Write the frames to a file, separated by blank lines
+pub fn write_frames(path: &str, frames: &[String]) -> io::Result<()> {
+    fs::write(path, frames.join("\n\n") + "\n")
+}
@@ -0,0 +1,144 @@
+use crate::{Ferry, Seat, SeatingError, SeatingRules};
+use point_2d::Point2D;
+use std::{collections::{HashMap, HashSet}, ops::Range, thread};
+
+/// A seating simulation over seats numbered in reading order, with each seat's neighbours
+/// looked up once up front and the next generation written into a second buffer
+pub struct DenseSimulation {
+    /// Where each seat is on the ferry
+    seats: Vec<Seat>,
+    /// The neighbours of seat i are neighbours[neighbour_starts[i]..neighbour_starts[i + 1]]
+    neighbour_starts: Vec<usize>,
+    neighbours: Vec<usize>,
+    /// The seats that start each row, plus the seat count
+    row_starts: Vec<usize>,
+    tolerance: usize,
+    occupied: Vec<bool>,
+    next: Vec<bool>,
+    threads: usize,
+}
+
+impl DenseSimulation {
+    /// Set up a simulation with every seat empty, stepping rows across this many threads
+    pub fn new(ferry: &Ferry, rules: &SeatingRules, threads: usize) -> Self {
+        let mut seats: Vec<Seat> = ferry.seats.iter().copied().collect();
+        seats.sort_unstable_by_key(|&Point2D(x, y)| (y, x));
+        let index: HashMap<Seat, usize> = seats
+            .iter()
+            .enumerate()
+            .map(|(i, &seat)| (seat, i))
+            .collect();
+
+        let neighbours_for_seats = ferry.neighbours(rules.neighbourhood);
+        let mut neighbour_starts: Vec<usize> = vec![0];
+        let mut neighbours: Vec<usize> = Vec::new();
+        for seat in seats.iter() {
+            neighbours.extend(neighbours_for_seats[seat].iter().map(|neighbour| index[neighbour]));
+            neighbour_starts.push(neighbours.len());
+        }
+
+        let mut row_starts: Vec<usize> = (0..seats.len())
+            .filter(|&i| i == 0 || seats[i].1 != seats[i - 1].1)
+            .collect();
+        row_starts.push(seats.len());
+
+        Self {
+            tolerance: rules.tolerance,
+            occupied: vec![false; seats.len()],
+            next: vec![false; seats.len()],
+            threads: threads.max(1),
+            seats,
+            neighbour_starts,
+            neighbours,
+            row_starts,
+        }
+    }
+
+    /// Work out the next state of the seats in range, writing them to next
+    fn step_seats(&self, seats: Range<usize>, next: &mut [bool]) -> bool {
+        let mut changed = false;
+        for (seat, next) in seats.zip(next.iter_mut()) {
+            let neighbours = &self.neighbours[self.neighbour_starts[seat]..self.neighbour_starts[seat + 1]];
+            let occupied_neighbours = neighbours
+                .iter()
+                .filter(|&&neighbour| self.occupied[neighbour])
+                .count();
+            *next = if self.occupied[seat] {
+                occupied_neighbours < self.tolerance
+            } else {
+                occupied_neighbours == 0
+            };
+            changed |= *next != self.occupied[seat];
+        }
+        changed
+    }
+
+    /// Move to the next generation, returning whether any seat changed
+    pub fn step(&mut self) -> bool {
+        let mut next = std::mem::take(&mut self.next);
+
+        let rows = self.row_starts.len() - 1;
+        let changed = if self.threads == 1 || rows <= 1 {
+            self.step_seats(0..self.seats.len(), &mut next)
+        } else {
+            // Give each thread a run of whole rows
+            let rows_per_thread = rows.div_ceil(self.threads);
+            let mut bounds: Vec<usize> = self.row_starts[..rows]
+                .iter()
+                .step_by(rows_per_thread)
+                .copied()
+                .collect();
+            bounds.push(self.seats.len());
+
+            let this = &*self;
+            thread::scope(|scope| {
+                let mut rest = &mut next[..];
+                let workers: Vec<_> = bounds
+                    .windows(2)
+                    .map(|bound| {
+                        let (start, end) = (bound[0], bound[1]);
+                        let (chunk, remaining) = std::mem::take(&mut rest).split_at_mut(end - start);
+                        rest = remaining;
+                        scope.spawn(move || this.step_seats(start..end, chunk))
+                    })
+                    .collect();
+                workers
+                    .into_iter()
+                    .map(|worker| worker.join().unwrap())
+                    .fold(false, |changed, chunk_changed| changed | chunk_changed)
+            })
+        };
+
+        self.next = std::mem::replace(&mut self.occupied, next);
+        changed
+    }
+
+    pub fn occupied_count(&self) -> usize {
+        self.occupied.iter().filter(|&&occupied| occupied).count()
+    }
+
+    pub fn occupied_seats(&self) -> HashSet<Seat> {
+        self.seats
+            .iter()
+            .zip(self.occupied.iter())
+            .filter(|(_, &occupied)| occupied)
+            .map(|(&seat, _)| seat)
+            .collect()
+    }
+
+    /// Step until the seating stops changing, then count the occupied seats. Generations are
+    /// numbered from all seats empty at 0, as in Simulation::history.
+    pub fn occupied_when_stable(&mut self) -> Result<usize, SeatingError> {
+        let mut seen: HashMap<Vec<bool>, usize> = HashMap::new();
+        let mut generation = 0;
+        seen.insert(self.occupied.clone(), generation);
+
+        while self.step() {
+            generation += 1;
+            if let Some(start) = seen.insert(self.occupied.clone(), generation) {
+                return Err(SeatingError::Cycle(start, generation - start));
+            }
+        }
+        Ok(self.occupied_count())
+    }
+}
@@ -1,14 +1,70 @@
+mod animation;
+mod dense;
+
 use itertools::Itertools;
 use point_2d::Point2D;
-use std::{env, fs, collections::{HashMap, HashSet}};
+use std::{env, fs, collections::{HashMap, HashSet}, fmt::Display, time::Duration};
+use crate::animation::{play, render_frames, write_frames};
+use crate::dense::DenseSimulation;
 
 type Seat = Point2D<isize>;
 
-/// A set of seats, with a map to easily find seats adjacent to another
+/// Which seats count as a seat's neighbours
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Neighbourhood {
+    /// The eight surrounding spaces
+    Adjacent,
+    /// The first seat in each of the eight directions, looking at most this many spaces if given
+    LineOfSight(Option<usize>),
+}
+
+/// How people choose seats
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct SeatingRules {
+    neighbourhood: Neighbourhood,
+    /// How many occupied neighbours make someone leave their seat
+    tolerance: usize,
+}
+
+const ADJACENT_RULES: SeatingRules =
+    SeatingRules { neighbourhood: Neighbourhood::Adjacent, tolerance: 4 };
+const LINE_OF_SIGHT_RULES: SeatingRules =
+    SeatingRules { neighbourhood: Neighbourhood::LineOfSight(None), tolerance: 5 };
+
+impl SeatingRules {
+    /// Parse rules like "adjacent,4", "sight,5" or "sight,5,3" (at most 3 spaces away)
+    fn from_spec(spec: &str) -> Option<Self> {
+        let parts: Vec<&str> = spec.split(',').collect();
+        let neighbourhood = match parts[..] {
+            ["adjacent", _] => Neighbourhood::Adjacent,
+            ["sight", _] => Neighbourhood::LineOfSight(None),
+            ["sight", _, distance] => Neighbourhood::LineOfSight(Some(distance.parse().ok()?)),
+            _ => return None,
+        };
+        Some(Self { neighbourhood, tolerance: parts[1].parse().ok()? })
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+enum SeatingError {
+    /// The generation the repeating seating starts at and how many generations it repeats over
+    Cycle(usize, usize),
+}
+
+impl Display for SeatingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SeatingError::Cycle(start, period) =>
+                write!(f, "seating repeats every {} generations from generation {}", period, start),
+        }
+    }
+}
+
+/// A set of seats in a grid of floor
 struct Ferry {
+    width: isize,
+    height: isize,
     seats: HashSet<Seat>,
-    adjacents_for_seats: HashMap<Seat, Vec<Seat>>,
-    visibles_for_seats: HashMap<Seat, Vec<Seat>>,
 }
 
 impl Ferry {
@@ -31,114 +87,120 @@ impl Ferry {
                         })
             ).collect();
 
-        let adjacents_for_seats = seats
+        Self { width, height, seats }
+    }
+
+    /// Map each seat to the seats that count as its neighbours
+    fn neighbours(&self, neighbourhood: Neighbourhood) -> HashMap<Seat, Vec<Seat>> {
+        let max_distance = match neighbourhood {
+            Neighbourhood::Adjacent => Some(1),
+            Neighbourhood::LineOfSight(max_distance) => max_distance,
+        };
+
+        self.seats
             .iter()
-            .map(|&seat|
+            .map(|&seat| {
+                let Point2D(x, y) = seat;
+                let dir_range = -1isize..=1isize;
                 (seat,
-                 seat
-                     .adjacent_points()
-                     .into_iter()
-                     .filter(|adj_seat| seats.contains(adj_seat))
-                     .collect()
-                )
-            ).collect();
+                dir_range
+                    .clone()
+                    .cartesian_product(dir_range)
+                    .filter_map(|(x_dir, y_dir)| {
+                        if x_dir == 0 && y_dir == 0 {
+                            return None;
+                        }
 
-        let visibles_for_seats =
-            seats
-                .iter()
-                .map(|&seat| {
-                    let Point2D(x, y) = seat;
-                    let dir_range = -1isize..=1isize;
-                    (seat,
-                    dir_range
-                        .clone()
-                        .cartesian_product(dir_range)
-                        .filter_map(|(x_dir, y_dir)| {
-                            if x_dir == 0 && y_dir == 0 {
-                                return None;
+                        let mut check_x = x + x_dir;
+                        let mut check_y = y + y_dir;
+                        let mut distance = 1;
+                        loop {
+                            let viewed_seat = Point2D(check_x, check_y);
+                            if check_x < 0 ||
+                               check_x >= self.width ||
+                               check_y < 0 ||
+                               check_y >= self.height ||
+                               max_distance.is_some_and(|max| distance > max) {
+                                   break None;
+                            } else if self.seats.contains(&viewed_seat) {
+                                break Some(viewed_seat);
+                            } else {
+                                check_x += x_dir;
+                                check_y += y_dir;
+                                distance += 1;
                             }
+                        }
+                    })
+                    .collect())
+            }).collect()
+    }
 
-                            let mut check_x = x + x_dir;
-                            let mut check_y = y + y_dir;
-                            loop {
-                                let viewed_seat = Point2D(check_x, check_y);
-                                if check_x < 0 ||
-                                   check_x >= width ||
-                                   check_y < 0 ||
-                                   check_y >= height {
-                                       break None;
-                                } else if seats.contains(&viewed_seat){
-                                    break Some(viewed_seat);
-                                } else {
-                                    check_x += x_dir;
-                                    check_y += y_dir;
-                                }
-                            }
-                        })
-                        .collect())
-                }).collect();
-
-        Self { seats, adjacents_for_seats, visibles_for_seats }
+    /// Draw the ferry with L for empty seats, # for occupied seats and . for floor
+    fn render(&self, occupied_seats: &HashSet<Seat>) -> String {
+        (0..self.height)
+            .map(|y| (0..self.width)
+                .map(|x| {
+                    let point = Point2D(x, y);
+                    if occupied_seats.contains(&point) {
+                        '#'
+                    } else if self.seats.contains(&point) {
+                        'L'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<String>()
+            )
+            .join("\n")
     }
+}
 
-    /// Given a set of occupied seats, find out what seats will be occupied in the next step
-    /// based on the first model (adjacent seats)
-    fn step1(&self, occupied_seats: &HashSet<Seat>) -> HashSet<Seat> {
-        // If no seats are occupied, all seats are filled
-        if occupied_seats.is_empty() {
-            return self.seats.clone();
-        }
+/// Every generation of a simulation, starting with all seats empty
+struct History {
+    generations: Vec<HashSet<Seat>>,
+    /// Ok if the last generation is stable
+    outcome: Result<(), SeatingError>,
+}
 
-        self.adjacents_for_seats
-            .iter()
-            .filter_map(|(seat, adjacents)| {
-                if occupied_seats.contains(seat) { // Occupied
-                    let stays_occupied = adjacents.len() < 4 || // Will always be occupied
-                        adjacents
-                            .iter()
-                            .filter(|adj| occupied_seats.contains(adj))
-                            .count() < 4;
+/// A ferry with a set of seating rules applied
+struct Simulation<'a> {
+    ferry: &'a Ferry,
+    tolerance: usize,
+    neighbours_for_seats: HashMap<Seat, Vec<Seat>>,
+}
 
-                    if stays_occupied {
-                        Some(seat)
-                    } else {
-                        None
-                    }
-                } else if adjacents.iter().all(|adj| !occupied_seats.contains(adj)) {
-                    // All surrounding seats are open
-                    Some(seat)
-                } else {
-                    None
-                }
-            })
-            .copied()
-            .collect()
+impl<'a> Simulation<'a> {
+    fn new(ferry: &'a Ferry, rules: &SeatingRules) -> Self {
+        Self {
+            ferry,
+            tolerance: rules.tolerance,
+            neighbours_for_seats: ferry.neighbours(rules.neighbourhood),
+        }
     }
 
     /// Given a set of occupied seats, find out what seats will be occupied in the next step
-    /// based on the second model (visible seats)
-    fn step2(&self, occupied_seats: &HashSet<Seat>) -> HashSet<Seat> {
+    fn step(&self, occupied_seats: &HashSet<Seat>) -> HashSet<Seat> {
         // If no seats are occupied, all seats are filled
         if occupied_seats.is_empty() {
-            return self.seats.clone();
+            return self.ferry.seats.clone();
         }
 
-        self.visibles_for_seats
+        self.neighbours_for_seats
             .iter()
-            .filter_map(|(seat, visible)| {
+            .filter_map(|(seat, neighbours)| {
                 if occupied_seats.contains(seat) { // Occupied
-                    let stays_occupied = visible.len() < 5 || // Will always be occupied
-                        visible
+                    let stays_occupied = neighbours.len() < self.tolerance || // Will always be occupied
+                        neighbours
                             .iter()
                             .filter(|adj| occupied_seats.contains(adj))
-                            .count() < 5;
+                            .count() < self.tolerance;
 
                     if stays_occupied {
                         Some(seat)
                     } else {
                         None
                     }
-                } else if visible.iter().all(|adj| !occupied_seats.contains(adj)) {
+                } else if neighbours.iter().all(|adj| !occupied_seats.contains(adj)) {
                     // All surrounding seats are open
                     Some(seat)
                 } else {
@@ -148,32 +210,49 @@ impl Ferry {
             .copied()
             .collect()
     }
-}
 
-/// Find the number of occupied seats when the state stabilizes
-fn part1(ferry: &Ferry) -> usize {
-    let mut state: HashSet<Seat> = HashSet::new();
-    loop {
-        let next_state = ferry.step1(&state);
-        if state == next_state {
-            break;
+    /// Step until the seating stops changing or returns to an earlier generation
+    fn history(&self) -> History {
+        let key = |occupied: &HashSet<Seat>| -> Vec<(isize, isize)> {
+            let mut key: Vec<(isize, isize)> = occupied.iter().map(|&Point2D(x, y)| (x, y)).collect();
+            key.sort_unstable();
+            key
+        };
+
+        let mut generations: Vec<HashSet<Seat>> = vec![HashSet::new()];
+        let mut seen: HashMap<Vec<(isize, isize)>, usize> = HashMap::new();
+        seen.insert(Vec::new(), 0);
+        loop {
+            let next_state = self.step(generations.last().unwrap());
+            if *generations.last().unwrap() == next_state {
+                break History { generations, outcome: Ok(()) };
+            }
+            if let Some(&start) = seen.get(&key(&next_state)) {
+                let period = generations.len() - start;
+                break History { generations, outcome: Err(SeatingError::Cycle(start, period)) };
+            }
+            seen.insert(key(&next_state), generations.len());
+            generations.push(next_state);
         }
-        state = next_state;
     }
-    state.len()
+}
+
+/// Find the number of occupied seats when the state stabilizes
+fn part1(ferry: &Ferry) -> Result<usize, SeatingError> {
+    DenseSimulation::new(ferry, &ADJACENT_RULES, 1).occupied_when_stable()
 }
 
 /// Find the number of occupied seats when the state stabilizes (model 2)
-fn part2(ferry: &Ferry) -> usize {
-    let mut state: HashSet<Seat> = HashSet::new();
-    loop {
-        let next_state = ferry.step2(&state);
-        if state == next_state {
-            break;
-        }
-        state = next_state;
+fn part2(ferry: &Ferry) -> Result<usize, SeatingError> {
+    DenseSimulation::new(ferry, &LINE_OF_SIGHT_RULES, 1).occupied_when_stable()
+}
+
+/// Describe how a simulation ended
+fn describe_outcome(history: &History) -> String {
+    match &history.outcome {
+        Ok(()) => format!("Stable after {} generations", history.generations.len() - 1),
+        Err(e) => format!("No stable seating: {}", e),
     }
-    state.len()
 }
 
 fn main() {
@@ -182,8 +261,52 @@ fn main() {
 
     let contents = fs::read_to_string(filename).expect("Error opening file");
     let ferry = Ferry::from_string(contents);
-    println!("Part 1: {}", part1(&ferry));
-    println!("Part 2: {}", part2(&ferry));
+
+    // Pass "animate [rules] [delay ms]" to watch the seating settle, "frames <path> [rules]" to
+    // write its generations to a file, or "rules <rules> [threads]" to settle it with other rules.
+    // Rules are 1 or 2 for the puzzle parts, or a spec like "sight,5,3" (see SeatingRules::from_spec).
+    let rules = |arg: Option<&String>| -> SeatingRules {
+        match arg.map(String::as_str) {
+            None | Some("1") => ADJACENT_RULES,
+            Some("2") => LINE_OF_SIGHT_RULES,
+            Some(spec) => SeatingRules::from_spec(spec)
+                .unwrap_or_else(|| panic!("Invalid rules \"{}\"", spec)),
+        }
+    };
+    match args.get(2).map(String::as_str) {
+        Some("animate") => {
+            let delay = args.get(4).map_or(200, |ms| ms.parse().expect("Delay must be a number"));
+            let history = Simulation::new(&ferry, &rules(args.get(3))).history();
+            play(&render_frames(&ferry, &history.generations), Duration::from_millis(delay));
+            println!("{}", describe_outcome(&history));
+            return;
+        }
+        Some("frames") => {
+            let path = args.get(3).expect("Frames needs a path to write to");
+            let history = Simulation::new(&ferry, &rules(args.get(4))).history();
+            write_frames(path, &render_frames(&ferry, &history.generations))
+                .expect("Error writing frames");
+            println!("{}", describe_outcome(&history));
+            return;
+        }
+        Some("rules") => {
+            let threads = args.get(4).map_or(1, |t| t.parse().expect("Threads must be a number"));
+            let mut simulation = DenseSimulation::new(&ferry, &rules(args.get(3)), threads);
+            match simulation.occupied_when_stable() {
+                Ok(occupied) => {
+                    println!("{}", ferry.render(&simulation.occupied_seats()));
+                    println!("Occupied: {}", occupied);
+                }
+                Err(e) => println!("{}", e),
+            }
+            return;
+        }
+        _ => {}
+    }
+
+    let show = |answer: Result<usize, SeatingError>| answer.map_or_else(|e| e.to_string(), |a| a.to_string());
+    println!("Part 1: {}", show(part1(&ferry)));
+    println!("Part 2: {}", show(part2(&ferry)));
 }
 
 #[cfg(test)]
@@ -195,12 +318,77 @@ mod tests {
     #[test]
     fn part1_examples() {
         let ferry = Ferry::from_string(SAMPLE.to_string());
-        assert_eq!(part1(&ferry), 37);
+        assert_eq!(part1(&ferry), Ok(37));
     }
 
     #[test]
     fn part2_examples() {
         let ferry = Ferry::from_string(SAMPLE.to_string());
-        assert_eq!(part2(&ferry), 26);
+        assert_eq!(part2(&ferry), Ok(26));
+    }
+
+    #[test]
+    fn generations() {
+        let ferry = Ferry::from_string(SAMPLE.to_string());
+        let history = Simulation::new(&ferry, &ADJACENT_RULES).history();
+        assert_eq!(history.outcome, Ok(()));
+        let generations = history.generations;
+        assert_eq!(generations.len() - 1, 5);
+        assert_eq!(generations.last().unwrap().len(), 37);
+
+        let frames = render_frames(&ferry, &generations);
+        assert_eq!(frames[0], format!("Generation 0\n{}", SAMPLE.trim_end()));
+        assert!(frames[1].ends_with("#.##.##.##\n#######.##\n#.#.#..#..\n####.##.##\n#.##.##.##\n\
+                                    #.#####.##\n..#.#.....\n##########\n#.######.#\n#.#####.##"));
+        assert_eq!(Simulation::new(&ferry, &LINE_OF_SIGHT_RULES).history().generations.len() - 1, 6);
+    }
+
+    #[test]
+    fn configurable_rules() {
+        assert_eq!(SeatingRules::from_spec("adjacent,4"), Some(ADJACENT_RULES));
+        assert_eq!(SeatingRules::from_spec("sight,5"), Some(LINE_OF_SIGHT_RULES));
+        assert_eq!(SeatingRules::from_spec("sight,5,1"), Some(SeatingRules {
+            neighbourhood: Neighbourhood::LineOfSight(Some(1)),
+            tolerance: 5,
+        }));
+        assert_eq!(SeatingRules::from_spec("sideways,4"), None);
+
+        // Seeing one space is the same as looking at adjacent seats
+        let ferry = Ferry::from_string(SAMPLE.to_string());
+        let short_sight = SeatingRules::from_spec("sight,4,1").unwrap();
+        assert!(ferry.neighbours(short_sight.neighbourhood) == ferry.neighbours(Neighbourhood::Adjacent));
+        assert_eq!(DenseSimulation::new(&ferry, &short_sight, 1).occupied_when_stable(), Ok(37));
+
+        // Two seats that can't stand any company take turns filling and emptying
+        let pair = Ferry::from_string("LL.\n".to_string());
+        let touchy = SeatingRules { neighbourhood: Neighbourhood::Adjacent, tolerance: 1 };
+        assert_eq!(DenseSimulation::new(&pair, &touchy, 1).occupied_when_stable(), Err(SeatingError::Cycle(0, 2)));
+
+        // Looking past the floor, but not far enough to see the other seat
+        let apart = Ferry::from_string("L..L\n".to_string());
+        let near_sighted = SeatingRules::from_spec("sight,1,2").unwrap();
+        assert_eq!(DenseSimulation::new(&apart, &near_sighted, 1).occupied_when_stable(), Ok(2));
+        let far_sighted = SeatingRules::from_spec("sight,1,3").unwrap();
+        assert_eq!(DenseSimulation::new(&apart, &far_sighted, 1).occupied_when_stable(), Err(SeatingError::Cycle(0, 2)));
+    }
+
+    #[test]
+    fn dense_matches_sets() {
+        let ferry = Ferry::from_string(SAMPLE.to_string());
+        for rules in [ADJACENT_RULES, LINE_OF_SIGHT_RULES].iter() {
+            let history = Simulation::new(&ferry, rules).history();
+            for threads in 1..=4 {
+                let mut dense = DenseSimulation::new(&ferry, rules, threads);
+                for generation in history.generations.iter().skip(1) {
+                    assert!(dense.step());
+                    assert!(dense.occupied_seats() == *generation);
+                }
+                assert!(!dense.step());
+            }
+        }
+
+        let mut dense = DenseSimulation::new(&ferry, &ADJACENT_RULES, 3);
+        assert_eq!(dense.occupied_when_stable(), Ok(37));
+        assert_eq!(dense.occupied_count(), 37);
     }
 }
@@ -0,0 +1,112 @@
+use crate::instruction::{Action, Instruction};
+use crate::Ship;
+use point_2d::Point2D;
+
+/// Coordinates that can end up anywhere, not just on the grid
+pub type ContinuousPosition = Point2D<f64>;
+
+/// Turn a vector anticlockwise by degrees. Right angles are done exactly so the puzzle inputs
+/// give whole-number answers; anything else goes through sine and cosine.
+fn rotate(vector: ContinuousPosition, degrees: f64) -> ContinuousPosition {
+    let Point2D(x, y) = vector;
+    let degrees = degrees.rem_euclid(360.0);
+    if degrees == 0.0 {
+        vector
+    } else if degrees == 90.0 {
+        Point2D(-y, x)
+    } else if degrees == 180.0 {
+        Point2D(-x, -y)
+    } else if degrees == 270.0 {
+        Point2D(y, -x)
+    } else {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Point2D(x * cos - y * sin, x * sin + y * cos)
+    }
+}
+
+/// The direction to turn a vector for a turn instruction, in degrees anticlockwise
+fn turn(instruction: &Instruction) -> Option<f64> {
+    match instruction.action {
+        Action::Left => Some(instruction.value as f64),
+        Action::Right => Some(-instruction.value as f64),
+        _ => None,
+    }
+}
+
+fn compass(action: Action) -> ContinuousPosition {
+    let Point2D(x, y) = action.direction().expect("Not a compass direction");
+    Point2D(x as f64, y as f64)
+}
+
+/// A MovingFerry that can face any direction
+pub struct FreeFerry {
+    /// Unit vector the ferry is facing
+    heading: ContinuousPosition,
+    position: ContinuousPosition,
+}
+
+impl FreeFerry {
+    pub fn new() -> Self {
+        Self {
+            heading: Point2D(1.0, 0.0),
+            position: Point2D(0.0, 0.0),
+        }
+    }
+}
+
+impl Ship for FreeFerry {
+    type Coordinate = f64;
+
+    fn step(&mut self, instruction: &Instruction) {
+        let value = instruction.value as f64;
+        if let Some(degrees) = turn(instruction) {
+            self.heading = rotate(self.heading, degrees);
+        } else if instruction.action == Action::Forward {
+            self.position += self.heading * value;
+        } else {
+            self.position += compass(instruction.action) * value;
+        }
+    }
+
+    fn position(&self) -> ContinuousPosition {
+        self.position
+    }
+}
+
+/// A WaypointFerry whose waypoint can be turned by any angle
+pub struct FreeWaypointFerry {
+    position: ContinuousPosition,
+    waypoint: ContinuousPosition,
+}
+
+impl FreeWaypointFerry {
+    pub fn new() -> Self {
+        Self {
+            position: Point2D(0.0, 0.0),
+            waypoint: Point2D(10.0, 1.0),
+        }
+    }
+}
+
+impl Ship for FreeWaypointFerry {
+    type Coordinate = f64;
+
+    fn step(&mut self, instruction: &Instruction) {
+        let value = instruction.value as f64;
+        if let Some(degrees) = turn(instruction) {
+            self.waypoint = rotate(self.waypoint, degrees);
+        } else if instruction.action == Action::Forward {
+            self.position += self.waypoint * value;
+        } else {
+            self.waypoint += compass(instruction.action) * value;
+        }
+    }
+
+    fn position(&self) -> ContinuousPosition {
+        self.position
+    }
+
+    fn waypoint(&self) -> Option<ContinuousPosition> {
+        Some(self.waypoint)
+    }
+}
@@ -0,0 +1,96 @@
+use crate::Position;
+use point_2d::Point2D;
+use std::fmt::Display;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Action {
+    North,
+    South,
+    East,
+    West,
+    Left,
+    Right,
+    Forward,
+}
+
+impl Action {
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            'N' => Some(Action::North),
+            'S' => Some(Action::South),
+            'E' => Some(Action::East),
+            'W' => Some(Action::West),
+            'L' => Some(Action::Left),
+            'R' => Some(Action::Right),
+            'F' => Some(Action::Forward),
+            _ => None,
+        }
+    }
+
+    /// The unit vector for a compass direction, or None for turns and moving forward
+    pub fn direction(&self) -> Option<Position> {
+        match self {
+            Action::North => Some(Point2D(0, 1)),
+            Action::South => Some(Point2D(0, -1)),
+            Action::East => Some(Point2D(1, 0)),
+            Action::West => Some(Point2D(-1, 0)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Instruction {
+    pub action: Action,
+    pub value: isize,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum ParseError {
+    /// Line number and the unrecognized action
+    UnknownAction(usize, String),
+    MissingValue(usize),
+    /// Line number and the value that isn't a number
+    InvalidValue(usize, String),
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::UnknownAction(line, action) =>
+                write!(f, "line {}: unknown action \"{}\"", line, action),
+            ParseError::MissingValue(line) => write!(f, "line {}: missing value", line),
+            ParseError::InvalidValue(line, value) =>
+                write!(f, "line {}: invalid value \"{}\"", line, value),
+        }
+    }
+}
+
+/// Parse a single line like "F10" as an Instruction
+pub fn parse_instruction(line: usize, instruction: &str) -> Result<Instruction, ParseError> {
+    let instruction = instruction.trim();
+    let mut chars = instruction.chars();
+    let action_char = chars.next().ok_or(ParseError::MissingValue(line))?;
+    let action = Action::from_char(action_char)
+        .ok_or_else(|| ParseError::UnknownAction(line, action_char.to_string()))?;
+
+    let value_str = chars.as_str();
+    if value_str.is_empty() {
+        return Err(ParseError::MissingValue(line));
+    }
+    let value = value_str
+        .parse()
+        .map_err(|_| ParseError::InvalidValue(line, value_str.to_string()))?;
+
+    Ok(Instruction { action, value })
+}
+
+/// Parse each item from a string slice iterator as an Instruction
+pub fn parse_instructions<'a>(
+    instructions: impl Iterator<Item = &'a str>,
+) -> Result<Vec<Instruction>, ParseError> {
+    instructions
+        .enumerate()
+        .map(|(line, instruction)| parse_instruction(line, instruction))
+        .collect()
+}
@@ -1,7 +1,15 @@
+mod continuous;
+mod instruction;
+mod route;
+
+use num::Signed;
 use point_2d::Point2D;
-use std::{env, fs};
+use std::{env, fs, fmt::Display};
+use crate::continuous::{FreeFerry, FreeWaypointFerry};
+use crate::instruction::{parse_instructions, Action, Instruction};
+use crate::route::Route;
 
-enum RotationDir { LEFT, RIGHT }
+enum RotationDir { Left, Right }
 
 /// 2D coordinates
 type Position = Point2D<isize>;
@@ -13,6 +21,62 @@ const CW_FACINGS: &[Position] = &[
     Point2D(0, 1)
 ];
 
+#[derive(Debug, Eq, PartialEq)]
+enum NavigationError {
+    /// Instruction index and the turn that isn't a multiple of 90 degrees
+    NotRightAngle(usize, isize),
+}
+
+impl Display for NavigationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NavigationError::NotRightAngle(index, degrees) =>
+                write!(f, "instruction {}: {} degrees is not a right angle", index, degrees),
+        }
+    }
+}
+
+/// Strict mode: make sure every turn is a right angle, which the grid-locked ships rely on
+fn check_right_angles(instructions: &[Instruction]) -> Result<(), NavigationError> {
+    match instructions
+        .iter()
+        .enumerate()
+        .find(|(_, i)| matches!(i.action, Action::Left | Action::Right) && i.value % 90 != 0)
+    {
+        Some((index, instruction)) => Err(NavigationError::NotRightAngle(index, instruction.value)),
+        None => Ok(()),
+    }
+}
+
+/// A navigation model: something that follows instructions and ends up somewhere
+trait Ship {
+    /// isize for ships locked to the grid, f64 for ships that can turn any angle
+    type Coordinate: Signed + Copy;
+
+    /// Update the ship according to the given instruction
+    fn step(&mut self, instruction: &Instruction);
+
+    fn position(&self) -> Point2D<Self::Coordinate>;
+
+    /// Where the ship is steering towards, for ships that navigate by waypoint
+    fn waypoint(&self) -> Option<Point2D<Self::Coordinate>> {
+        None
+    }
+
+    fn manhattan_distance(&self) -> Self::Coordinate {
+        let Point2D(x, y) = self.position();
+        x.abs() + y.abs()
+    }
+}
+
+/// Run a ship through every instruction, returning it where it finishes
+fn navigate<S: Ship>(mut ship: S, instructions: &[Instruction]) -> S {
+    for instruction in instructions {
+        ship.step(instruction);
+    }
+    ship
+}
+
 /// A representation of a moving ferry
 struct MovingFerry {
     facing: Position,
@@ -27,37 +91,38 @@ impl MovingFerry {
         }
     }
 
-    /// Rotate the ship, assuming the facing is locked to a cardinal direction
+    /// Rotate the ship, assuming the facing is locked to a cardinal direction. Only right angles
+    /// make sense here, see check_right_angles.
     fn rotate(&mut self, direction: RotationDir, degrees: isize) {
         let dir_iter: Box<dyn Iterator<Item = &Position>> = match direction {
-            RotationDir::LEFT => Box::new(CW_FACINGS.iter().rev().cycle()),
-            RotationDir::RIGHT => Box::new(CW_FACINGS.iter().cycle()),
+            RotationDir::Left => Box::new(CW_FACINGS.iter().rev().cycle()),
+            RotationDir::Right => Box::new(CW_FACINGS.iter().cycle()),
         };
 
-        let facing_skip = degrees / 90;
+        let facing_skip = degrees.rem_euclid(360) / 90;
         self.facing = *dir_iter
             .skip_while(|&&facing| facing != self.facing)
-            .skip(facing_skip as usize)
-            .next()
+            .nth(facing_skip as usize)
             .unwrap();
-
     }
+}
+
+impl Ship for MovingFerry {
+    type Coordinate = isize;
 
-    /// Update the ferry according to the given instruction
-    fn step(&mut self, instruction: &str) {
-        let (action, value_str) = instruction.split_at(1);
-        let value: isize = value_str.parse().unwrap();
-        match action {
-            "N" => self.position += Point2D(0, 1) * value,
-            "S" => self.position += Point2D(0, -1) * value,
-            "E" => self.position += Point2D(1, 0) * value,
-            "W" => self.position += Point2D(-1, 0) * value,
-            "L" => self.rotate(RotationDir::LEFT, value),
-            "R" => self.rotate(RotationDir::RIGHT, value),
-            "F" => self.position += self.facing * value,
-            a => panic!("Unrecognized action {}", a),
+    fn step(&mut self, instruction: &Instruction) {
+        let value = instruction.value;
+        match instruction.action {
+            Action::Left => self.rotate(RotationDir::Left, value),
+            Action::Right => self.rotate(RotationDir::Right, value),
+            Action::Forward => self.position += self.facing * value,
+            compass => self.position += compass.direction().unwrap() * value,
         }
     }
+
+    fn position(&self) -> Position {
+        self.position
+    }
 }
 
 struct WaypointFerry {
@@ -73,51 +138,51 @@ impl WaypointFerry {
         }
     }
 
-    /// Rotate the ship, assuming the facing is locked to a cardinal direction
+    /// Rotate the waypoint around the ship in steps of 90 degrees
     fn rotate(&mut self, direction: RotationDir, degrees: isize) {
-        let facing_skip = degrees / 90;
+        let facing_skip = degrees.rem_euclid(360) / 90;
         for _ in 0..facing_skip {
             let Point2D(x, y) = self.waypoint;
             match direction {
-                RotationDir::LEFT => self.waypoint = Point2D(-1 * y, x),
-                RotationDir::RIGHT => self.waypoint = Point2D(y, -1 * x),
+                RotationDir::Left => self.waypoint = Point2D(-y, x),
+                RotationDir::Right => self.waypoint = Point2D(y, -x),
             }
         }
     }
+}
+
+impl Ship for WaypointFerry {
+    type Coordinate = isize;
 
-    /// Update the ferry according to the given instruction
-    fn step(&mut self, instruction: &str) {
-        let (action, value_str) = instruction.split_at(1);
-        let value: isize = value_str.parse().unwrap();
-        match action {
-            "N" => self.waypoint += Point2D(0, 1) * value,
-            "S" => self.waypoint += Point2D(0, -1) * value,
-            "E" => self.waypoint += Point2D(1, 0) * value,
-            "W" => self.waypoint += Point2D(-1, 0) * value,
-            "L" => self.rotate(RotationDir::LEFT, value),
-            "R" => self.rotate(RotationDir::RIGHT, value),
-            "F" => self.position += self.waypoint * value,
-            a => panic!("Unrecognized action {}", a),
+    fn step(&mut self, instruction: &Instruction) {
+        let value = instruction.value;
+        match instruction.action {
+            Action::Left => self.rotate(RotationDir::Left, value),
+            Action::Right => self.rotate(RotationDir::Right, value),
+            Action::Forward => self.position += self.waypoint * value,
+            compass => self.waypoint += compass.direction().unwrap() * value,
         }
     }
+
+    fn position(&self) -> Position {
+        self.position
+    }
+
+    fn waypoint(&self) -> Option<Position> {
+        Some(self.waypoint)
+    }
 }
 
 /// Find the manhattan distance traveled after a ferry follows the instructions
-fn part1<'a>(instructions: impl Iterator<Item = &'a str>) -> isize {
-    let mut ferry = MovingFerry::new();
-    for instruction in instructions {
-        ferry.step(instruction);
-    }
-    ferry.position.0.abs() + ferry.position.1.abs()
+fn part1(instructions: &[Instruction]) -> Result<isize, NavigationError> {
+    check_right_angles(instructions)?;
+    Ok(navigate(MovingFerry::new(), instructions).manhattan_distance())
 }
 
 /// Find the manhattan distance traveled after a ferry follows the *real* instructions
-fn part2<'a>(instructions: impl Iterator<Item = &'a str>) -> isize {
-    let mut ferry = WaypointFerry::new();
-    for instruction in instructions {
-        ferry.step(instruction);
-    }
-    ferry.position.0.abs() + ferry.position.1.abs()
+fn part2(instructions: &[Instruction]) -> Result<isize, NavigationError> {
+    check_right_angles(instructions)?;
+    Ok(navigate(WaypointFerry::new(), instructions).manhattan_distance())
 }
 
 fn main() {
@@ -125,8 +190,36 @@ fn main() {
     let filename = &args[1];
 
     let contents = fs::read_to_string(filename).expect("Error opening file");
-    println!("Part 1: {}", part1(contents.lines()));
-    println!("Part 2: {}", part2(contents.lines()));
+    let instructions = parse_instructions(contents.lines())
+        .unwrap_or_else(|e| panic!("Invalid instructions: {}", e));
+
+    // Pass "route <file.svg|file.csv> [1|2]" to save the route a ferry takes. Routes allow any
+    // turning angle.
+    if args.get(2).map(String::as_str) == Some("route") {
+        let path = args.get(3).expect("Route needs a path to write to");
+        let route = match args.get(4).map(String::as_str) {
+            Some("2") => Route::record(FreeWaypointFerry::new(), &instructions),
+            _ => Route::record(FreeFerry::new(), &instructions),
+        };
+        let contents = if path.ends_with(".csv") { route.to_csv() } else { route.to_svg() };
+        fs::write(path, contents).expect("Error writing route");
+
+        let (step, Point2D(x, y)) = route.furthest();
+        println!("Total path length: {}", route.length());
+        println!("Furthest point: ({}, {}) after {} instructions", x, y, step);
+        return;
+    }
+
+    // Pass "continuous" after the filename to allow turns that aren't right angles
+    if args.get(2).map(String::as_str) == Some("continuous") {
+        println!("Part 1: {}", navigate(FreeFerry::new(), &instructions).manhattan_distance());
+        println!("Part 2: {}", navigate(FreeWaypointFerry::new(), &instructions).manhattan_distance());
+        return;
+    }
+
+    let show = |answer: Result<isize, NavigationError>| answer.map_or_else(|e| e.to_string(), |a| a.to_string());
+    println!("Part 1: {}", show(part1(&instructions)));
+    println!("Part 2: {}", show(part2(&instructions)));
 }
 
 #[cfg(test)]
@@ -136,12 +229,95 @@ mod test {
     #[test]
     fn part1_example() {
         let instructions = vec!["F10", "N3", "F7", "R90", "F11"];
-        assert_eq!(part1(instructions.into_iter()), 25);
+        assert_eq!(part1(&parse_instructions(instructions.into_iter()).unwrap()), Ok(25));
     }
 
     #[test]
     fn part2_example() {
         let instructions = vec!["F10", "N3", "F7", "R90", "F11"];
-        assert_eq!(part2(instructions.into_iter()), 286);
+        assert_eq!(part2(&parse_instructions(instructions.into_iter()).unwrap()), Ok(286));
+    }
+
+    #[test]
+    fn parse_errors() {
+        use crate::instruction::ParseError;
+
+        assert_eq!(
+            parse_instructions(vec!["F10", "N3", "X7"].into_iter()),
+            Err(ParseError::UnknownAction(2, "X".to_string()))
+        );
+        assert_eq!(parse_instructions(vec!["R"].into_iter()), Err(ParseError::MissingValue(0)));
+        assert_eq!(
+            parse_instructions(vec!["F1", "R9x"].into_iter()),
+            Err(ParseError::InvalidValue(1, "9x".to_string()))
+        );
+    }
+
+    #[test]
+    fn shared_driver() {
+        /// Goes straight forward, ignoring everything else
+        struct Tugboat(isize);
+
+        impl Ship for Tugboat {
+            type Coordinate = isize;
+
+            fn step(&mut self, instruction: &Instruction) {
+                if instruction.action == Action::Forward {
+                    self.0 += instruction.value;
+                }
+            }
+
+            fn position(&self) -> Position {
+                Point2D(self.0, 0)
+            }
+        }
+
+        let instructions = parse_instructions(vec!["F10", "N3", "F7", "R90", "F11"].into_iter()).unwrap();
+        assert_eq!(navigate(Tugboat(0), &instructions).manhattan_distance(), 28);
+        assert!(navigate(MovingFerry::new(), &instructions).position() == Point2D(17, -8));
+    }
+
+    #[test]
+    fn any_angle() {
+        let sample = parse_instructions(vec!["F10", "N3", "F7", "R90", "F11"].into_iter()).unwrap();
+        assert_eq!(navigate(FreeFerry::new(), &sample).manhattan_distance(), 25.0);
+        assert_eq!(navigate(FreeWaypointFerry::new(), &sample).manhattan_distance(), 286.0);
+
+        // Turns past a full circle or backwards wrap around
+        let wrapped = parse_instructions(vec!["R450", "F5", "L-90", "F1"].into_iter()).unwrap();
+        assert_eq!(part1(&wrapped), Ok(6));
+        assert_eq!(navigate(FreeFerry::new(), &wrapped).manhattan_distance(), 6.0);
+
+        let diagonal = parse_instructions(vec!["L45", "F10", "R135", "F10"].into_iter()).unwrap();
+        assert_eq!(part1(&diagonal), Err(NavigationError::NotRightAngle(0, 45)));
+        assert_eq!(part2(&diagonal), Err(NavigationError::NotRightAngle(0, 45)));
+
+        let Point2D(x, y) = navigate(FreeFerry::new(), &diagonal).position();
+        let side = 10.0 / 2f64.sqrt();
+        assert!((x - side).abs() < 1e-9 && (y - (side - 10.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn routes() {
+        let sample = parse_instructions(vec!["F10", "N3", "F7", "R90", "F11"].into_iter()).unwrap();
+
+        let route = Route::record(MovingFerry::new(), &sample);
+        assert_eq!(route.points.len(), 6);
+        assert_eq!(route.length(), 31.0);
+        assert!(route.furthest() == (5, Point2D(17.0, -8.0)));
+        assert!(route.points.iter().all(|point| point.waypoint.is_none()));
+        assert_eq!(
+            route.to_csv(),
+            "step,x,y,waypoint_x,waypoint_y\n0,0,0,,\n1,10,0,,\n2,10,3,,\n3,17,3,,\n4,17,3,,\n5,17,-8,,\n"
+        );
+
+        let route = Route::record(FreeWaypointFerry::new(), &sample);
+        assert!(route.points[4].waypoint == Some(Point2D(4.0, -10.0)));
+        assert!(route.furthest().1 == Point2D(214.0, -72.0));
+        assert!(route.to_csv().ends_with("5,214,-72,4,-10\n"));
+
+        let svg = route.to_svg();
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.contains("points=\"0,0 100,-10 100,-10 170,-38 170,-38 214,72\""));
     }
 }
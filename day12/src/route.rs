@@ -0,0 +1,132 @@
+use crate::instruction::Instruction;
+use crate::Ship;
+use num::ToPrimitive;
+use point_2d::Point2D;
+use std::fmt::Write;
+
+/// Where a ship was after one instruction
+#[derive(Clone, Copy)]
+pub struct RoutePoint {
+    pub position: Point2D<f64>,
+    pub waypoint: Option<Point2D<f64>>,
+}
+
+/// Every place a ship has been, starting before the first instruction
+pub struct Route {
+    pub points: Vec<RoutePoint>,
+}
+
+fn to_f64<T: ToPrimitive>(Point2D(x, y): Point2D<T>) -> Point2D<f64> {
+    Point2D(
+        x.to_f64().expect("Coordinate out of range"),
+        y.to_f64().expect("Coordinate out of range"),
+    )
+}
+
+fn distance(from: Point2D<f64>, to: Point2D<f64>) -> f64 {
+    let Point2D(x, y) = to - from;
+    x.hypot(y)
+}
+
+impl Route {
+    /// Run a ship through the instructions, noting where it is after each one
+    pub fn record<S: Ship>(mut ship: S, instructions: &[Instruction]) -> Self
+    where
+        S::Coordinate: ToPrimitive,
+    {
+        let mut points: Vec<RoutePoint> = Vec::with_capacity(instructions.len() + 1);
+        let mut note = |ship: &S| points.push(RoutePoint {
+            position: to_f64(ship.position()),
+            waypoint: ship.waypoint().map(to_f64),
+        });
+
+        note(&ship);
+        for instruction in instructions {
+            ship.step(instruction);
+            note(&ship);
+        }
+        Self { points }
+    }
+
+    /// How far the ship sailed, in straight lines between positions
+    pub fn length(&self) -> f64 {
+        self.points
+            .windows(2)
+            .map(|pair| distance(pair[0].position, pair[1].position))
+            .sum()
+    }
+
+    /// The step with the position furthest from the start by manhattan distance, and that
+    /// position. The earliest step wins a tie.
+    pub fn furthest(&self) -> (usize, Point2D<f64>) {
+        let start = self.points[0].position;
+        let manhattan = |point: &RoutePoint| {
+            let Point2D(x, y) = point.position - start;
+            x.abs() + y.abs()
+        };
+
+        let (step, point) = self.points
+            .iter()
+            .enumerate()
+            .fold((0, &self.points[0]), |furthest, (step, point)| {
+                if manhattan(point) > manhattan(furthest.1) { (step, point) } else { furthest }
+            });
+        (step, point.position)
+    }
+
+    /// One line per step: the step, position and waypoint (blank if the ship doesn't have one)
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("step,x,y,waypoint_x,waypoint_y\n");
+        for (step, point) in self.points.iter().enumerate() {
+            let Point2D(x, y) = point.position;
+            let waypoint = point.waypoint
+                .map_or(",".to_string(), |Point2D(wx, wy)| format!("{},{}", wx, wy));
+            writeln!(csv, "{},{},{},{}", step, x, y, waypoint).unwrap();
+        }
+        csv
+    }
+
+    /// Draw the route as an SVG polyline, marking the start and the furthest point. North is up.
+    pub fn to_svg(&self) -> String {
+        let (min_x, max_x, min_y, max_y) = self.points.iter().fold(
+            (0f64, 0f64, 0f64, 0f64),
+            |(min_x, max_x, min_y, max_y), point| {
+                let Point2D(x, y) = point.position;
+                (min_x.min(x), max_x.max(x), min_y.min(y), max_y.max(y))
+            },
+        );
+        let margin = ((max_x - min_x).max(max_y - min_y) / 20.0).max(1.0);
+        let stroke = margin / 4.0;
+        // SVG's y axis points down, so flip north to the top (subtracting to avoid printing -0)
+        let svg_point = |Point2D(x, y): Point2D<f64>| format!("{},{}", x, 0.0 - y);
+
+        let polyline = self.points
+            .iter()
+            .map(|point| svg_point(point.position))
+            .collect::<Vec<String>>()
+            .join(" ");
+        let marker = |point: Point2D<f64>, color: &str| format!(
+            "  <circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\"/>\n",
+            point.0, 0.0 - point.1, stroke * 2.0, color
+        );
+
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">",
+            min_x - margin,
+            -max_y - margin,
+            max_x - min_x + 2.0 * margin,
+            max_y - min_y + 2.0 * margin
+        ).unwrap();
+        writeln!(
+            svg,
+            "  <polyline points=\"{}\" fill=\"none\" stroke=\"black\" stroke-width=\"{}\"/>",
+            polyline, stroke
+        ).unwrap();
+        svg.push_str(&marker(self.points[0].position, "green"));
+        svg.push_str(&marker(self.furthest().1, "red"));
+        svg.push_str("</svg>\n");
+        svg
+    }
+}